@@ -1,5 +1,5 @@
 use bevy::{
-    ecs::{system::EntityCommands}, prelude::*, reflect::Reflect, transform::components::Transform, utils::HashMap
+    ecs::{system::EntityCommands}, math::I64Vec2, prelude::*, reflect::Reflect, transform::components::Transform, utils::HashMap
 };
 use bevy_entitiles::
     ldtk::{
@@ -7,103 +7,78 @@ use bevy_entitiles::
         resources::LdtkAssets,
 };
 use bevy_xpbd_2d::prelude::*;
-use bevy_asepritesheet::prelude::*;
 use bevy_entitiles_derive::LdtkEntity;
 use seldom_state::prelude::*;
 use bevy_yarnspinner::prelude::*;
-use bevy_yarnspinner_example_dialogue_view::prelude::*;
-use bevy::app::AppExit;
 use crate::util::*;
-use bevy_entitiles::ldtk::resources::LdtkLevelManager;
-
-const PLAYER_SPEED: f32 = 100.;
-const ENEMY_AGRO: f32 = 60.;
+use crate::movement::{Knockback, MovementController, MovementSet};
+use crate::aim::AimDirection;
+use crate::facing::{FaceAim, Facing};
+use crate::animation::{AnimState, AnimationController};
+use crate::inventory::Inventory;
+use crate::morale::{Morale, MoraleConfig};
+use crate::patrol::{PatrolConfig, Post};
+use crate::perception::Perception;
+use crate::shop::shop_closed;
+use crate::targeting::{target_lost, Target, Targeting, Targets};
+
+/// Radius of the player's body collider.
+pub const PLAYER_RADIUS: f32 = 5.;
 
 pub struct ActorPlugin;
 
 impl Plugin for ActorPlugin {
    fn build(&self, app: &mut App) {
-       app.add_plugins((
-                AsepritesheetPlugin::new(&["sprite.json"]).in_schedule(Update),
+       app.add_plugins(
                 StateMachinePlugin,
-                YarnSpinnerPlugin::new(),
-                ExampleYarnSpinnerDialogueViewPlugin::new(),
-            ))
+            )
             .add_systems(Startup, (
                 setup,
             ))
             .add_systems(
                 Update,
                 (
-                    player_control,
-                    pick_up_items,
+                    // Left clicks in the shop are for its buttons.
+                    player_control.run_if(shop_closed),
                     open_inventory,
                     extra_player_setup,
                     player_rotation,
-                    attack_collisions,
-                    follow,
+                    player_anims,
                     text_setup.run_if(resource_added::<YarnProject>),
                     enemy_ai,
-                    enemy_add_sprites,
                     enemy_say_flee.run_if(resource_exists::<YarnProject>),
                     enemy_say_follow.run_if(resource_exists::<YarnProject>),
-                    idle,
-                    follow,
-                    flee,
                     enemy_hit,
+                    player_hit,
                     death,
                     just_died,
-                    hit,
-                    end,
                 ),
             )
-            .add_systems(Update, reload)
-            .add_event::<EnemyHit>();
+            .add_systems(Update, (idle, follow, flee).in_set(MovementSet::Steer))
+            .insert_resource(Gravity(Vec2::new(0., 0.)))
+            .add_event::<EnemyHit>()
+            .add_event::<PlayerHit>();
     } 
 }
 
+// Sprites are attached by the `ViewPlugin`, here they are just anchors so the
+// rest of the logic works headless.
 fn setup(
     mut commands: Commands,
-    assets: Res<AssetServer>
 ) {
     let mut camera = Camera2dBundle::default();
     camera.projection.scale = 0.2;
 
-    let sheet_handle = load_spritesheet_then(
-        &mut commands,
-        &assets,
-        "sprite.json",
-        bevy::sprite::Anchor::Center,
-        |sheet| {
-            info!("Spritesheet finished loading!");
-            format_anims_attack(sheet);
-        },
-    );
-
     let anim_attack = commands
-        .spawn(AnimatedSpriteBundle {
-            spritesheet: sheet_handle,
-            ..Default::default()
-        })
+        .spawn((
+            SpatialBundle::default(),
+            AttackSprite,
+        ))
         .id();
 
-    let sheet_handle = load_spritesheet_then(
-        &mut commands,
-        &assets,
-        "character.json",
-        bevy::sprite::Anchor::Center,
-        |sheet| {
-            info!("Spritesheet finished loading!");
-            format_anims_player(sheet);
-        },
-    );
-
     let anim_player = commands
         .spawn((
-            AnimatedSpriteBundle {
-                spritesheet: sheet_handle,
-                ..Default::default()
-            },
+            SpatialBundle::default(),
             PlayerSprite,
         )).id();
 
@@ -116,13 +91,23 @@ fn setup(
         ))
         .id();
 
-    commands.entity(anim_player).add_child(anim_attack);
-    commands.entity(anim_player).add_child(attack_hitbox);
+    // The character sprite picks a directional animation, only the attack
+    // swings around with the aim.
+    let attack_pivot = commands
+        .spawn((
+            SpatialBundle::default(),
+            AttackPivot,
+        ))
+        .id();
+
+    commands.entity(attack_pivot).add_child(anim_attack);
+    commands.entity(attack_pivot).add_child(attack_hitbox);
+    commands.entity(anim_player).add_child(attack_pivot);
 
     commands
         .spawn((
             camera,
-            Collider::circle(5.0),
+            Collider::circle(PLAYER_RADIUS),
             RigidBody::Dynamic,
             LockedAxes::ROTATION_LOCKED,
             Friction {
@@ -131,34 +116,16 @@ fn setup(
                 ..Default::default()
             },
             Mass(100.),
-            LinearDamping(10.),
+            MovementController::default(),
+            Knockback::default(),
+            AimDirection::default(),
+            Facing::default(),
+            FaceAim,
             PlayerHitBox,
         ))
         .add_child(anim_player);
 }
 
-fn format_anims_attack(sheet: &mut Spritesheet) {
-    let handle_attack = sheet.get_anim_handle("attack");
-
-    if let Ok(attack) = sheet.get_anim_mut(&handle_attack) {
-        attack.end_action = AnimEndAction::Stop;
-    }
-}
-
-fn format_anims_player(sheet: &mut Spritesheet) {
-    let handle_idle = sheet.get_anim_handle("idle");
-    let handle_hit = sheet.get_anim_handle("hit");
-    let _handle_walk = sheet.get_anim_handle("walk");
-
-    if let Ok(anim) = sheet.get_anim_mut(&handle_idle) {
-        anim.end_action = AnimEndAction::Pause;
-    }
-
-    if let Ok(anim) = sheet.get_anim_mut(&handle_hit) {
-        anim.end_action = AnimEndAction::Next(handle_idle);
-    }
-}
-
 fn extra_player_setup(
     mut commands: Commands,
     mut camera_q: Query<
@@ -179,110 +146,94 @@ fn extra_player_setup(
     }
 }
 
-fn enemy_add_sprites(
+// Builds the state machine of every enemy that doesn't have one, which
+// includes enemies whose machine was thrown away by `ResetAi`.
+fn enemy_ai(
     mut commands: Commands,
-    enemy_q: Query<Entity, (With<Enemy>, With<AddSprite>)>,
-    assets: Res<AssetServer>
+    enemy_q: Query<Entity, (With<Enemy>, Without<StateMachine>)>,
 ) {
     for enemy in enemy_q.iter() {
-        let sheet_handle = load_spritesheet_then(
-            &mut commands,
-            &assets,
-            "enemy.json",
-            bevy::sprite::Anchor::Center,
-            |sheet| {
-                info!("Spritesheet finished loading!");
-                format_anims_player(sheet);
-            },
-        );
+        // Enemies only react to a player they see or heard recently,
+        // see `Perception`.
+        let notices_player = move |In(entity): In<Entity>, perceptions: Query<&Perception>, morales: Query<&Morale>, config: Res<MoraleConfig>| {
+            let aware = perceptions.get(entity).is_ok_and(Perception::aware);
+            let fear = morales.get(entity).map_or(0., |morale| morale.fear);
 
-        let anim = commands
-            .spawn((
-                AnimatedSpriteBundle {
-                    spritesheet: sheet_handle,
-                    ..Default::default()
-                },
-                EnemySprite,
-            ))
-            .id();
+            aware && fear <= config.follow_below
+        };
 
-        commands.entity(enemy).add_child(anim);
-        commands.entity(enemy).remove::<AddSprite>();
-    }
-}
+        // `threshold` picks which of the fear thresholds has to be reached.
+        let notices_player_with_fear = move |threshold: fn(&MoraleConfig) -> f32| {
+            move |In(entity): In<Entity>, perceptions: Query<&Perception>, morales: Query<&Morale>, config: Res<MoraleConfig>| {
+                let aware = perceptions.get(entity).is_ok_and(Perception::aware);
+                let fear = morales.get(entity).map_or(0., |morale| morale.fear);
 
-fn enemy_ai(
-    mut commands: Commands,
-    enemy_q: Query<Entity, (With<Enemy>, Without<StateMachine>)>,
-    player_q: Query<Entity, With<PlayerMover>>,
-) {
-    for enemy in enemy_q.iter() {
-        match player_q.get_single() {
-            Ok(player) => {
-
-                let near_player = move |In(entity): In<Entity>, transforms: Query<&Transform>, enemies: Query<&Enemy>| {
-                    let distance = transforms
-                        .get(player)
-                        .unwrap()
-                        .translation
-                        .truncate()
-                        .distance(transforms.get(entity).unwrap().translation.truncate());
-
-                    let fear = enemies.get(entity).unwrap().fear;
-
-                    // Check whether the target is within range. If it is, return `Ok` to trigger!
-                    match distance <= ENEMY_AGRO && fear <= 10. {
-                        true => Ok(distance),
-                        false => Err(distance),
-                    }
-                };
-
-                let near_player_and_afraid = move |In(entity): In<Entity>, transforms: Query<&Transform>, enemies: Query<&Enemy>| {
-                    let distance = transforms
-                        .get(player)
-                        .unwrap()
-                        .translation
-                        .truncate()
-                        .distance(transforms.get(entity).unwrap().translation.truncate());
-                    
-                    let fear = enemies.get(entity).unwrap().fear;
-
-                    // Check whether the target is within range. If it is, return `Ok` to trigger!
-                    match distance <= ENEMY_AGRO && fear >= 50. {
-                        true => Ok(distance),
-                        false => Err(distance),
-                    }
-                };
-
-                let dead = move |In(entity): In<Entity>, enemies: Query<&Enemy>| {
-                    let health = enemies.get(entity).unwrap().hp;
-
-                    // Check whether the target is within range. If it is, return `Ok` to trigger!
-                    match health <= 0. {
-                        true =>  Ok(health),
-                        false => Err(health),
-                    }
-                };
-
-                commands.entity(enemy).insert((
-                    StateMachine::default()
-                        .trans::<Idle, _>(near_player, Follow { target: player, speed: 15.})
-                        .trans::<Follow, _>(near_player.not(), Idle)
-                        .trans::<Idle, _>(near_player_and_afraid, Flee {target: player, speed: 25.})
-                        .trans::<Follow, _>(near_player_and_afraid, Flee {target: player, speed: 25.})
-                        .trans::<Flee, _>(near_player_and_afraid.not(), Idle)
-                        .trans::<Flee, _>(dead, Dead)
-                        .trans::<Idle, _>(dead, Dead)
-                        .trans::<Follow, _>(dead, Dead)
-                        .on_enter::<Follow>(move |entity| { entity.insert(FollowDialogueTimer::default()); })
-                        .on_enter::<Flee>(move |entity| { entity.insert(FleeDialogueTimer::default()); })
-                        .on_enter::<Dead>(move |entity| { entity.insert(JustDied); })
-                    ,
-                    Idle,
-                ));
-            },
-            _ => {},
-        }
+                aware && fear >= threshold(&config)
+            }
+        };
+        let notices_player_and_afraid = notices_player_with_fear(|config| config.flee_above);
+        let cornered = notices_player_with_fear(|config| config.cower_above);
+
+        let calmed_down = move |In(entity): In<Entity>, morales: Query<&Morale>, config: Res<MoraleConfig>| {
+            morales.get(entity).map_or(true, |morale| morale.fear < config.flee_above)
+        };
+
+        let broken = move |In(entity): In<Entity>, morales: Query<&Morale>, config: Res<MoraleConfig>| {
+            morales.get(entity).is_ok_and(|morale| morale.fear >= config.surrender_above)
+        };
+
+        let has_route = |In(entity): In<Entity>, posts: Query<&Post>| {
+            posts.get(entity).is_ok_and(|post| !post.route.is_empty())
+        };
+
+        let at_post = |In(entity): In<Entity>, posts: Query<(&Post, &Transform)>, config: Res<PatrolConfig>| {
+            posts.get(entity).is_ok_and(|(post, transform)| {
+                transform.translation.truncate().distance(post.anchor()) <= config.arrive_distance
+            })
+        };
+
+        let dead = move |In(entity): In<Entity>, enemies: Query<&Enemy>| {
+            enemies.get(entity).is_ok_and(|enemy| enemy.hp <= 0.)
+        };
+
+        // Transitions are checked in order. Dying wins over everything, a
+        // vanished target over what perception says about it, and fear over
+        // losing interest in the player.
+        commands.entity(enemy).insert((
+            StateMachine::default()
+                .trans::<Flee, _>(dead, Dead)
+                .trans::<Idle, _>(dead, Dead)
+                .trans::<Patrol, _>(dead, Dead)
+                .trans::<Wander, _>(dead, Dead)
+                .trans::<ReturnToPost, _>(dead, Dead)
+                .trans::<Follow, _>(dead, Dead)
+                .trans::<Cower, _>(dead, Dead)
+                .trans::<Surrender, _>(dead, Dead)
+                .trans::<Follow, _>(target_lost::<Follow>, Idle)
+                .trans::<Flee, _>(target_lost::<Flee>, Idle)
+                .trans::<Idle, _>(notices_player_and_afraid, Flee { target: Target::Player, speed: 25.})
+                .trans::<Patrol, _>(notices_player_and_afraid, Flee { target: Target::Player, speed: 25.})
+                .trans::<Wander, _>(notices_player_and_afraid, Flee { target: Target::Player, speed: 25.})
+                .trans::<ReturnToPost, _>(notices_player_and_afraid, Flee { target: Target::Player, speed: 25.})
+                .trans::<Follow, _>(notices_player_and_afraid, Flee { target: Target::Player, speed: 25.})
+                .trans::<Idle, _>(notices_player, Follow { target: Target::Player, speed: 15.})
+                .trans::<Patrol, _>(notices_player, Follow { target: Target::Player, speed: 15.})
+                .trans::<Wander, _>(notices_player, Follow { target: Target::Player, speed: 15.})
+                .trans::<ReturnToPost, _>(notices_player, Follow { target: Target::Player, speed: 15.})
+                .trans::<Follow, _>(notices_player.not(), ReturnToPost)
+                .trans::<Flee, _>(cornered, Cower)
+                .trans::<Flee, _>(notices_player_and_afraid.not(), ReturnToPost)
+                .trans::<Idle, _>(has_route, Patrol::default())
+                .trans::<Idle, _>(has_route.not(), Wander::default())
+                .trans::<ReturnToPost, _>(at_post, Idle)
+                .trans::<Cower, _>(broken, Surrender)
+                .trans::<Cower, _>(calmed_down, Idle)
+                .on_enter::<Follow>(move |entity| { entity.insert(FollowDialogueTimer::default()); })
+                .on_enter::<Flee>(move |entity| { entity.insert(FleeDialogueTimer::default()); })
+                .on_enter::<Dead>(move |entity| { entity.insert(JustDied); })
+            ,
+            Idle,
+        ));
     }
 }
 
@@ -304,9 +255,11 @@ fn enemy_say_follow(
         timer.0.tick(time.delta());
 
         if timer.0.just_finished() {
-            let mut dr = dialogue_runner.single_mut();
-            let _ = dr.variable_storage_mut().set("$name".to_string(), YarnValue::String(enemies.get(entity).unwrap().name.clone()));
-            let level = (player.single().compleation / 25.).floor() as i8;
+            let (Ok(mut dr), Ok(enemy), Ok(player)) = (dialogue_runner.get_single_mut(), enemies.get(entity), player.get_single()) else {
+                continue;
+            };
+            let _ = dr.variable_storage_mut().set("$name".to_string(), YarnValue::String(enemy.name.clone()));
+            let level = (player.compleation / 25.).floor() as i8;
 
             match dr.current_node() {
                 Some(_) => {
@@ -334,9 +287,11 @@ fn enemy_say_flee(
         timer.0.tick(time.delta());
 
         if timer.0.just_finished() {
-            let mut dr = dialogue_runner.single_mut();
-            let _ = dr.variable_storage_mut().set("$name".to_string(), YarnValue::String(enemies.get(entity).unwrap().name.clone()));
-            let level = (player.single().compleation / 25.).floor() as i8;
+            let (Ok(mut dr), Ok(enemy), Ok(player)) = (dialogue_runner.get_single_mut(), enemies.get(entity), player.get_single()) else {
+                continue;
+            };
+            let _ = dr.variable_storage_mut().set("$name".to_string(), YarnValue::String(enemy.name.clone()));
+            let level = (player.compleation / 25.).floor() as i8;
 
             match dr.current_node() {
                 Some(_) => {
@@ -360,197 +315,117 @@ fn just_died(
     dead: Query<Entity, With<JustDied>>,
     enemies: Query<Entity, (With<Enemy>, Without<Dead>)>,
 ) {
+    let Ok(mut player) = player.get_single_mut() else {
+        return;
+    };
+
     for entity in dead.iter() {
-        player.single_mut().compleation += 4.;
+        player.compleation += 4.;
+        player.killed += 1;
         commands.entity(entity).remove::<JustDied>();
         
-        if player.single_mut().compleation >= 100. {
-            let mut dr = dialogue_runner.single_mut();
-            match dr.current_node() {
-                Some(_) => {
-                    dr.stop();
-                },
-                None => {
-                },
-            }
-            dr.start_node("End");
-            commands.entity(entity).insert(EndTimer::default());
-        }
-
         if enemies.iter().len() == 0 {
-            let mut dr = dialogue_runner.single_mut();
-            match dr.current_node() {
-                Some(_) => {
-                    dr.stop();
-                },
-                None => {
-                },
-            }
-            dr.start_node("Reset");
-        }
-    }
-}
-
-fn end(
-    end: Query<&EndTimer>,
-    mut exit: EventWriter<AppExit>
-) {
-    for e in end.iter() {
-        if e.0.just_finished() {
-            exit.send(AppExit);
-            return;
-        }
-    }
-}
-
-pub fn reload(
-    mut commands: Commands,
-    mut manager: ResMut<LdtkLevelManager>,
-    enemies: Query<Entity, (With<Enemy>, Without<Dead>)>,
-    input: Res<ButtonInput<KeyCode>>,
-) {
-    if enemies.iter().len() == 0 {
-        if input.just_released(KeyCode::KeyR) {
-            manager.unload_all(&mut commands);
-            manager.load(&mut commands, "Start".to_string(), None);
-        }
-    }
-}
-
-
-fn attack_collisions(
-    mut collision_event_reader: EventReader<Collision>,
-    mut events: EventWriter<EnemyHit>,
-    enemies_q: Query<Entity, (With<EnemyHitBox>, Without<Dead>)>,
-    input: Res<ButtonInput<MouseButton>>,
-    player_entity_q: Query<Entity, With<PlayerAttackBox>>,
-) {
-    if input.just_pressed(MouseButton::Left) {
-        let Ok(player_e) = player_entity_q.get_single() else {
-            return;
-        };
-
-        for Collision(contacts) in collision_event_reader.read() {
-            for enemy_e in enemies_q.iter() {
-                if (contacts.entity1 == player_e && contacts.entity2 == enemy_e) ||
-                    (contacts.entity2 == player_e && contacts.entity1 == enemy_e) {
-                    events.send(EnemyHit { enemy:enemy_e });
+            if let Ok(mut dr) = dialogue_runner.get_single_mut() {
+                match dr.current_node() {
+                    Some(_) => {
+                        dr.stop();
+                    },
+                    None => {
+                    },
                 }
+                dr.start_node("Reset");
             }
         }
     }
 }
 
 fn enemy_hit(
-    mut enemies: Query<(&mut Enemy, &Children)>,
-    mut players: Query<&mut Player>,
+    mut enemies: Query<(&mut Enemy, &mut Knockback, &Children)>,
     mut events: EventReader<EnemyHit>,
-    mut commands: Commands,
+    mut anims: Query<&mut AnimationController>,
 ) {
     for event in events.read() {
-        let (mut enemy, children) = enemies.get_mut(event.enemy).unwrap();
-        let player = players.single_mut();
+        let Ok((mut enemy, mut knockback, children)) = enemies.get_mut(event.enemy) else {
+            continue;
+        };
 
-        enemy.hp -= player.sword_skill;
-        enemy.fear += player.sword_skill * 10.;
+        enemy.hp -= event.damage;
+        enemy.fear += event.damage * 10.;
+        knockback.apply(event.direction * event.knockback);
         //player.sword_skill += 0.1;
         
         for child in children {
-            commands.entity(*child).remove::<HitTimer>();
-            commands.entity(*child).insert(HitTimer::default());
+            if let Ok(mut anim) = anims.get_mut(*child) {
+                anim.trigger(AnimState::Hit);
+            }
         }
     }
 }
 
-fn open_inventory(input: Res<ButtonInput<KeyCode>>, player_q: Query<&Player>) {
-    if input.just_released(KeyCode::KeyI) {
-        let Ok(player) = player_q.get_single() else {
-            return;
-        };
-        println!("INVENTORY: {:?}", player.inventory.0);
+fn player_hit(
+    mut player_q: Query<&mut Player>,
+    mut mover_q: Query<&mut Knockback, With<PlayerMover>>,
+    mut sprite_q: Query<&mut AnimationController, With<PlayerSprite>>,
+    mut events: EventReader<PlayerHit>,
+) {
+    for event in events.read() {
+        if let Ok(mut player) = player_q.get_single_mut() {
+            player.hp = (player.hp - event.damage).max(0.);
+        }
+        if let Ok(mut knockback) = mover_q.get_single_mut() {
+            knockback.apply(event.direction * event.knockback);
+        }
+        if let Ok(mut anim) = sprite_q.get_single_mut() {
+            anim.trigger(AnimState::Hit);
+        }
     }
 }
 
-fn pick_up_items(
-    input: Res<ButtonInput<KeyCode>>,
-    items: Query<(Entity, &Transform, &Item)>,
-    mut inventory_q: Query<&mut Player>,
-    player_q: Query<&Transform, With<PlayerMover>>,
-    mut commands: Commands,
-) {
-    if input.just_released(KeyCode::KeyE) {
-        let Ok(transform) = player_q.get_single() else {
-            return;
-        };
-        let Ok(mut player) = inventory_q.get_single_mut() else {
+fn open_inventory(input: Res<ButtonInput<KeyCode>>, inventory_q: Query<&Inventory, With<Player>>) {
+    if input.just_released(KeyCode::KeyI) {
+        let Ok(inventory) = inventory_q.get_single() else {
             return;
         };
-        for (entity, position, item) in items.iter() {
-            if position.translation.distance(transform.translation) < 20.0 {
-                for _ in 0..item.count {
-                    player.inventory.0.push(item.itype);
-                }
-
-                commands.entity(entity).despawn();
-            }
-        }
+        info!("Equipped: {:?}", inventory.equipped);
+        info!("Inventory: {:?}", inventory.stacks());
     }
 }
 
 fn player_rotation(
-    mut player_q: Query<&mut Transform, With<PlayerSprite>>,
-    window_q: Query<&Window>,
+    mut sprite_q: Query<&mut Transform, With<AttackPivot>>,
+    aim_q: Query<&AimDirection, With<PlayerMover>>,
 ) {
-    let Ok(window) = window_q.get_single() else {
+    let Ok(aim) = aim_q.get_single() else {
+        return;
+    };
+    let Ok(mut transform) = sprite_q.get_single_mut() else {
         return;
     };
 
-    let pos = Vec2::new(window.width() / 2., window.height() / 2.);
-
-    match window.cursor_position() {
-        Some(cursor) => {
-            let angle = (cursor - pos).angle_between(pos);
+    transform.rotation = Quat::from_rotation_z(aim.angle());
+}
 
-            let Ok(mut transform) = player_q.get_single_mut() else {
-                return;
-            };
+fn player_anims(
+    player_q: Query<&LinearVelocity, With<PlayerMover>>,
+    mut sprite_q: Query<&mut AnimationController, With<PlayerSprite>>,
+) {
+    let Ok(velocity) = player_q.get_single() else {
+        return;
+    };
+    let Ok(mut anim) = sprite_q.get_single_mut() else {
+        return;
+    };
 
-            transform.rotation = Quat::from_rotation_z(angle - 0.5);
-        }
-        None => {}
-    }
+    anim.set_base(if velocity.length() > 1. { AnimState::Walk } else { AnimState::Idle });
 }
 
 fn player_control(
-    mut player_q: Query<&mut LinearVelocity, With<PlayerMover>>,
-    mut anim_q: Query<&mut SpriteAnimator>,
-    input: Res<ButtonInput<KeyCode>>,
+    mut anim_q: Query<&mut AnimationController, With<AttackSprite>>,
     buttons: Res<ButtonInput<MouseButton>>,
 ) {
-    let Ok(mut velocity) = player_q.get_single_mut() else {
-        return;
-    };
-    // wasd is taken up by the camera controller.
-    if input.pressed(KeyCode::KeyA) {
-        velocity.x = -PLAYER_SPEED;
-    }
-    if input.pressed(KeyCode::KeyD) {
-        velocity.x = PLAYER_SPEED;
-    }
-    if input.pressed(KeyCode::KeyW) {
-        velocity.y = PLAYER_SPEED;
-    }
-    if input.pressed(KeyCode::KeyS) {
-        velocity.y = -PLAYER_SPEED;
-    }
-
     if buttons.just_pressed(MouseButton::Left) {
-        for mut sprite_animator in anim_q.iter_mut() {
-            if sprite_animator.cur_anim().is_none() {
-                sprite_animator.set_anim_index(0);
-            } else {
-                sprite_animator.restart_anim();
-            }
+        for mut anim in anim_q.iter_mut() {
+            anim.trigger(AnimState::Attack);
         }
     }
 }
@@ -564,156 +439,153 @@ fn player_control(
 // Entities in the `Follow` task move toward the given entity at the given speed
 #[derive(Clone, Component, Reflect)]
 #[component(storage = "SparseSet")]
-struct Follow {
-    target: Entity,
-    speed: f32,
+pub struct Follow {
+    pub target: Target,
+    pub speed: f32,
+}
+
+impl Targeting for Follow {
+    fn target(&self) -> Target {
+        self.target
+    }
 }
 
 #[derive(Clone, Component, Reflect)]
 #[component(storage = "SparseSet")]
-struct Idle;
+pub struct Idle;
 
 // Entities in the `Follow` task move toward the given entity at the given speed
 #[derive(Clone, Component, Reflect)]
 #[component(storage = "SparseSet")]
-struct Flee {
-    target: Entity,
-    speed: f32,
+pub struct Flee {
+    pub target: Target,
+    pub speed: f32,
+}
+
+impl Targeting for Flee {
+    fn target(&self) -> Target {
+        self.target
+    }
 }
 
+/// Walking the `Post` route, stopping briefly at each waypoint.
+#[derive(Clone, Component, Reflect, Default)]
+#[component(storage = "SparseSet")]
+pub struct Patrol {
+    pub pause: Timer,
+}
+
+/// Strolling to random spots around the `Post`, resting in between.
+#[derive(Clone, Component, Reflect, Default)]
+#[component(storage = "SparseSet")]
+pub struct Wander {
+    pub goal: Option<Vec2>,
+    pub pause: Timer,
+}
+
+/// Lost the player, heading back to where it patrols or wanders.
+#[derive(Clone, Component, Reflect)]
+#[component(storage = "SparseSet")]
+pub struct ReturnToPost;
+
+/// Too scared to run, the enemy freezes in place.
+#[derive(Clone, Component, Reflect)]
+#[component(storage = "SparseSet")]
+pub struct Cower;
+
+/// The enemy has given up and waits for the player to decide its fate.
+#[derive(Clone, Component, Reflect)]
+#[component(storage = "SparseSet")]
+pub struct Surrender;
+
 #[derive(Clone, Component, Reflect)]
 #[component(storage = "SparseSet")]
-struct Dead;
+pub struct Dead;
 
 // Let's define some real behavior for entities in the follow task.
 fn follow(
-    mut transforms: Query<&mut Transform>,
-    mut anims: Query<&mut SpriteAnimator>,
-    follows: Query<(Entity, &Follow, &Children), Without<Flee>>,
-    timers: Query<Entity, With<HitTimer>>,
-    time: Res<Time>,
+    targets: Targets,
+    mut anims: Query<&mut AnimationController>,
+    mut follows: Query<(&Follow, &GlobalTransform, Option<&Perception>, &mut MovementController, &Children), Without<Flee>>,
 ) {
-    for (entity, follow, children) in &follows {
-        // Get the positions of the follower and target
-        let mut target_translation = transforms.get(follow.target).unwrap().translation;
-        target_translation.z = 0.;
-        let follow_transform = &mut transforms.get_mut(entity).unwrap();
-        let mut follow_translation = follow_transform.translation;
-        follow_translation.z = 0.;
-
-        // Find the direction from the follower to the target and go that way
-        follow_transform.translation += (target_translation - follow_translation)
-            .normalize_or_zero()
-            * follow.speed
-            * time.delta_seconds();
-        
+    for (follow, transform, perception, mut controller, children) in follows.iter_mut() {
+        // The state machine drops back to `Idle` once the target is gone.
+        let Some(target) = targets.position(follow.target) else {
+            controller.input = Vec2::ZERO;
+            continue;
+        };
+
+        // Out of sight the follower heads for where it last noticed the
+        // target instead.
+        let goal = perception.map_or(target, |perception| perception.chase_point(target));
+
+        // Find the direction from the follower to the target and steer that
+        // way, physics does the rest so walls and knockback still apply.
+        controller.input = (goal - transform.translation().truncate()).normalize_or_zero();
+        controller.max_speed = Some(follow.speed);
+
         for child in children {
-            match timers.get(*child) {
-                Ok(timer) => {
-                    if *child != timer {
-                        anims.get_mut(*child).unwrap().set_anim_index(2);
-                    }
-                },
-                Err(_) => {
-                    anims.get_mut(*child).unwrap().set_anim_index(2);
-                },
+            if let Ok(mut anim) = anims.get_mut(*child) {
+                anim.set_base(AnimState::Walk);
             }
         }
     }
 }
 
 fn idle(
-    mut anims: Query<&mut SpriteAnimator>,
-    idles: Query<&Children, (With<Enemy>, With<Idle>)>,
-    timers: Query<Entity, With<HitTimer>>,
+    mut anims: Query<&mut AnimationController>,
+    mut idles: Query<(&Children, &mut MovementController), (With<Enemy>, With<Idle>)>,
 ) {
-    for children in &idles {
+    for (children, mut controller) in idles.iter_mut() {
+        controller.input = Vec2::ZERO;
+
         for child in children {
-            match timers.get(*child) {
-                Ok(timer) => {
-                    if *child != timer {
-                        anims.get_mut(*child).unwrap().set_anim_index(0);
-                    }
-                },
-                Err(_) => {
-                    anims.get_mut(*child).unwrap().set_anim_index(0);
-                },
+            if let Ok(mut anim) = anims.get_mut(*child) {
+                anim.set_base(AnimState::Idle);
             }
         }
     }
 }
 
 fn death(
-    mut anims: Query<&mut SpriteAnimator>,
-    idles: Query<&Children, (With<Enemy>, With<Dead>)>
+    mut anims: Query<&mut AnimationController>,
+    mut idles: Query<(&Children, &mut MovementController), (With<Enemy>, With<Dead>)>
 ) {
-    for children in &idles {
-        for child in children {
-            anims.get_mut(*child).unwrap().set_anim_index(3);
-        }
-    }
-}
+    for (children, mut controller) in idles.iter_mut() {
+        controller.input = Vec2::ZERO;
 
-// Let's define some real behavior for entities in the follow task.
-fn flee(
-    mut transforms: Query<&mut Transform>,
-    mut anims: Query<&mut SpriteAnimator>,
-    follows: Query<(Entity, &Flee, &Children)>,
-    timers: Query<Entity, With<HitTimer>>,
-    time: Res<Time>,
-) {
-    for (entity, follow, children) in &follows {
-        // Get the positions of the follower and target
-        let mut target_translation = transforms.get(follow.target).unwrap().translation;
-        target_translation.z = 0.;
-        let follow_transform = &mut transforms.get_mut(entity).unwrap();
-        let mut follow_translation = follow_transform.translation;
-        follow_translation.z = 0.;
-
-        // Find the direction from the follower to the target and go that way
-        follow_transform.translation -= (target_translation - follow_translation)
-            .normalize_or_zero()
-            * follow.speed
-            * time.delta_seconds();
-        
         for child in children {
-            match timers.get(*child) {
-                Ok(timer) => {
-                    if *child != timer {
-                        anims.get_mut(*child).unwrap().set_anim_index(2);
-                    }
-                },
-                Err(_) => {
-                    anims.get_mut(*child).unwrap().set_anim_index(2);
-                },
+            if let Ok(mut anim) = anims.get_mut(*child) {
+                anim.set_base(AnimState::Dead);
             }
         }
     }
 }
 
-fn hit(
-    mut commands: Commands,
-    mut timer: Query<(Entity, &mut HitTimer)>,
-    mut anims: Query<&mut SpriteAnimator>,
-    time: Res<Time>,
+// Let's define some real behavior for entities in the follow task.
+fn flee(
+    targets: Targets,
+    mut anims: Query<&mut AnimationController>,
+    mut follows: Query<(&Flee, &GlobalTransform, &mut MovementController, &Children)>,
 ) {
-    for (entity, mut timer) in timer.iter_mut() {
-        if timer.0.elapsed().is_zero()  {
-            anims.get_mut(entity).unwrap().set_anim_index(1);
-        }
+    for (follow, transform, mut controller, children) in follows.iter_mut() {
+        let Some(target) = targets.position(follow.target) else {
+            controller.input = Vec2::ZERO;
+            continue;
+        };
 
-        timer.0.tick(time.delta());
+        // Run directly away from the target
+        controller.input = (transform.translation().truncate() - target).normalize_or_zero();
+        controller.max_speed = Some(follow.speed);
 
-        if timer.0.just_finished() {
-            anims.get_mut(entity).unwrap().stop_anim();
-            commands.entity(entity).remove::<HitTimer>();
+        for child in children {
+            if let Ok(mut anim) = anims.get_mut(*child) {
+                anim.set_base(AnimState::Walk);
+            }
         }
     }
 }
 
-
-
-
 fn player_spawn(
     commands: &mut EntityCommands,
     entity_instance: &EntityInstance,
@@ -736,7 +608,13 @@ fn enemy_spawn(
     _asset_server: &AssetServer,
     _ldtk_assets: &LdtkAssets,
 ) {
-    commands.insert((
+    commands.insert(enemy_bundle());
+}
+
+/// Physics and bookkeeping components every enemy needs on top of the LDtk
+/// `Enemy` data.
+pub fn enemy_bundle() -> impl Bundle {
+    (
         EnemyHitBox,
         Collider::rectangle(10., 10.),
         RigidBody::Dynamic,
@@ -747,10 +625,14 @@ fn enemy_spawn(
             ..Default::default()
         },
         Mass(100.),
-        LinearDamping(10.),
+        MovementController::default(),
+        Knockback::default(),
+        Morale::default(),
+        Perception::default(),
+        Facing::default(),
         AddSprite,
         Name::new("Enemy"),
-    ));
+    )
 }
 
 #[derive(Component, LdtkEntity, Default, Reflect)]
@@ -770,7 +652,11 @@ pub struct Player {
 
     // You can impl the LdtkEntity trait yourself so these wrappers
     // can be avoided.
-    pub inventory: ItemTypeVec,
+
+    /// The LDtk `inventory` field. Only read once, when the player spawns,
+    /// to fill the `Inventory` component.
+    #[ldtk_name = "inventory"]
+    pub starting_items: ItemTypeVec,
     #[ldtk_name = "HP"]
     pub hp: f32,
     #[ldtk_name = "MP"]
@@ -780,6 +666,12 @@ pub struct Player {
     #[ldtk_name = "RunSkill"]
     pub run_skill: f32,
     pub compleation: f32,
+    /// Enemies and bosses the player finished off.
+    #[ldtk_default]
+    pub killed: i32,
+    /// Beaten enemies the player let go.
+    #[ldtk_default]
+    pub spared: i32,
 }
 
 #[derive(Component, LdtkEntity, Default, Reflect)]
@@ -792,17 +684,45 @@ pub struct Enemy {
     pub attack: f32,
     pub fear: f32,
     pub name: String,
+    #[ldtk_default]
+    pub sprite: String,
+    /// Name of the loot table rolled on death, on top of `inventory`.
+    #[ldtk_default]
+    pub loot: String,
+    /// Enemies with the same squad name keep together and retreat as one.
+    #[ldtk_default]
+    pub squad: String,
+    #[ldtk_default]
+    pub squad_leader: bool,
+    /// Waypoints in LDtk grid cells, walked in a loop while nothing is
+    /// going on. Without any the enemy wanders around where it was placed.
+    #[ldtk_default]
+    pub patrol: Vec<I64Vec2>,
 }
 
 #[derive(Resource)]
 struct IdleTimer(Timer);
 
-#[derive(Component)]
-struct AddSprite;
 
 #[derive(Event)]
-struct EnemyHit {
-    enemy: Entity,
+pub struct EnemyHit {
+    pub enemy: Entity,
+    pub attacker: Entity,
+    pub damage: f32,
+    /// Normalised direction from the attacker to the enemy.
+    pub direction: Vec2,
+    /// Speed the enemy gets pushed away with along `direction`.
+    pub knockback: f32,
+}
+
+#[derive(Event)]
+pub struct PlayerHit {
+    pub attacker: Entity,
+    pub damage: f32,
+    /// Normalised direction from the attacker to the player.
+    pub direction: Vec2,
+    /// Speed the player gets pushed away with along `direction`.
+    pub knockback: f32,
 }
 
 #[derive(Component)]
@@ -839,35 +759,3 @@ impl Default for FleeDialogueTimer {
     }
     
 }
-
-#[derive(Component)]
-struct HitTimer(Timer);
-
-impl HitTimer {
-    pub fn new() -> Self {
-        Self(Timer::from_seconds(0.3, TimerMode::Once))
-    }
-}
-
-impl Default for HitTimer {
-    fn default() -> Self {
-        Self::new()
-    }
-    
-}
-
-#[derive(Component)]
-struct EndTimer(Timer);
-
-impl EndTimer {
-    pub fn new() -> Self {
-        Self(Timer::from_seconds(10.0, TimerMode::Once))
-    }
-}
-
-impl Default for EndTimer {
-    fn default() -> Self {
-        Self::new()
-    }
-    
-}
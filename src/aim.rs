@@ -0,0 +1,58 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+use crate::replay::not_replaying;
+use crate::util::*;
+
+pub struct AimPlugin;
+
+impl Plugin for AimPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<AimDirection>()
+            .add_systems(Update, player_aim.run_if(not_replaying));
+    }
+}
+
+/// Normalised direction an actor is aiming in, in world space.
+#[derive(Component, Reflect, Clone, Copy)]
+pub struct AimDirection(pub Vec2);
+
+impl AimDirection {
+    pub fn angle(&self) -> f32 {
+        self.0.y.atan2(self.0.x)
+    }
+}
+
+impl Default for AimDirection {
+    fn default() -> Self {
+        Self(Vec2::X)
+    }
+}
+
+// Project the cursor through the camera so the aim stays correct no matter
+// where the camera is or how big the window is.
+fn player_aim(
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    mut player_q: Query<(&GlobalTransform, &mut AimDirection), With<PlayerMover>>,
+) {
+    let Ok(window) = window_q.get_single() else {
+        return;
+    };
+    let Some(cursor) = window.cursor_position() else {
+        return;
+    };
+    let Ok((camera, camera_t)) = camera_q.get_single() else {
+        return;
+    };
+    let Some(cursor_world) = camera.viewport_to_world_2d(camera_t, cursor) else {
+        return;
+    };
+    let Ok((player_t, mut aim)) = player_q.get_single_mut() else {
+        return;
+    };
+
+    let direction = (cursor_world - player_t.translation().truncate()).normalize_or_zero();
+
+    if direction != Vec2::ZERO {
+        aim.0 = direction;
+    }
+}
@@ -0,0 +1,223 @@
+use bevy::{prelude::*, utils::{HashMap, HashSet}};
+use bevy_asepritesheet::prelude::*;
+use crate::facing::{resolve_directional, Direction8, Facing};
+use crate::sheets::SpritesheetRegistry;
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<AnimState>()
+            .add_systems(PostUpdate, drive_animations);
+    }
+}
+
+/// Logical animation states. Gameplay code only talks in these, the
+/// `AnimationController` maps them to Aseprite tags.
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum AnimState {
+    #[default]
+    Idle,
+    Walk,
+    Attack,
+    Hit,
+    Dead,
+}
+
+impl AnimState {
+    /// Higher priority states win over lower ones, so a hit interrupts a
+    /// walk but can't interrupt dying.
+    pub fn priority(&self) -> u8 {
+        match self {
+            AnimState::Idle => 0,
+            AnimState::Walk => 1,
+            AnimState::Attack => 2,
+            AnimState::Hit => 3,
+            AnimState::Dead => 4,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ClipEnd {
+    Loop,
+    /// Stay on the last frame.
+    Hold,
+    /// Stop and hide the animation.
+    Stop,
+}
+
+impl ClipEnd {
+    fn to_end_action(self) -> AnimEndAction {
+        match self {
+            ClipEnd::Loop => AnimEndAction::Loop,
+            ClipEnd::Hold => AnimEndAction::Pause,
+            ClipEnd::Stop => AnimEndAction::Stop,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AnimClip {
+    pub tag: String,
+    pub end_action: ClipEnd,
+    /// How long the clip overrides the base state when it is triggered.
+    pub duration: f32,
+}
+
+/// Plays the right directional tag for the current logical state of a
+/// sprite. Lives on the sprite entity, facing is read from the sprite or
+/// its parent.
+#[derive(Component, Clone, Default)]
+pub struct AnimationController {
+    clips: HashMap<AnimState, AnimClip>,
+    base: AnimState,
+    overlay: Option<(AnimState, Timer)>,
+    playing: Option<(AnimState, Direction8)>,
+    restart: bool,
+    missing: HashSet<AnimState>,
+}
+
+impl AnimationController {
+    pub fn with_clip(mut self, state: AnimState, tag: &str, end_action: ClipEnd) -> Self {
+        self.clips.insert(state, AnimClip {
+            tag: tag.to_string(),
+            end_action,
+            duration: 0.3,
+        });
+        self
+    }
+
+    pub fn with_duration(mut self, state: AnimState, duration: f32) -> Self {
+        if let Some(clip) = self.clips.get_mut(&state) {
+            clip.duration = duration;
+        }
+        self
+    }
+
+    /// Sets the looping state the sprite falls back to, e.g. Idle or Walk.
+    pub fn set_base(&mut self, state: AnimState) {
+        self.base = state;
+    }
+
+    /// Plays a one-shot state on top of the base state for the clip's
+    /// duration. Ignored if something with a higher priority is playing.
+    pub fn trigger(&mut self, state: AnimState) {
+        if self.state().priority() > state.priority() {
+            return;
+        }
+
+        let duration = self.clips.get(&state).map_or(0., |clip| clip.duration);
+        self.overlay = Some((state, Timer::from_seconds(duration, TimerMode::Once)));
+        self.restart = true;
+    }
+
+    pub fn state(&self) -> AnimState {
+        match &self.overlay {
+            Some((overlay, _)) if overlay.priority() >= self.base.priority() => *overlay,
+            _ => self.base,
+        }
+    }
+
+    fn is_formatted(&self, sheet: AssetId<Spritesheet>, registry: &SpritesheetRegistry) -> bool {
+        self.clips.values().all(|clip| registry.is_formatted(sheet, &clip.tag))
+    }
+
+    // Clips another controller already formatted on this sheet are skipped.
+    fn format(&self, sheet_id: AssetId<Spritesheet>, sheet: &mut Spritesheet, registry: &mut SpritesheetRegistry) {
+        for clip in self.clips.values() {
+            if registry.is_formatted(sheet_id, &clip.tag) {
+                continue;
+            }
+            registry.mark_formatted(sheet_id, &clip.tag);
+
+            let mut tags = vec![clip.tag.clone()];
+            for direction in Direction8::ALL {
+                tags.push(format!("{}_{}", clip.tag, direction.suffix()));
+            }
+
+            for tag in tags {
+                let handle = sheet.get_anim_handle(&tag);
+                if let Ok(anim) = sheet.get_anim_mut(&handle) {
+                    anim.end_action = clip.end_action.to_end_action();
+                }
+            }
+        }
+    }
+}
+
+fn drive_animations(
+    mut controllers: Query<(
+        &mut AnimationController,
+        &mut SpriteAnimator,
+        &mut Sprite,
+        &Handle<Spritesheet>,
+        Option<&Facing>,
+        Option<&Parent>,
+    )>,
+    facings: Query<&Facing>,
+    mut sheets: ResMut<Assets<Spritesheet>>,
+    mut registry: ResMut<SpritesheetRegistry>,
+    time: Res<Time>,
+) {
+    for (mut controller, mut animator, mut sprite, sheet_handle, facing, parent) in controllers.iter_mut() {
+        // Sheets are shared between sprites, possibly with different clips,
+        // so end actions are applied once per tag of a sheet.
+        if !controller.is_formatted(sheet_handle.id(), &registry) {
+            let Some(sheet) = sheets.get_mut(sheet_handle) else {
+                continue;
+            };
+            controller.format(sheet_handle.id(), sheet, &mut registry);
+        }
+
+        if let Some((_, timer)) = &mut controller.overlay {
+            timer.tick(time.delta());
+            if timer.finished() {
+                controller.overlay = None;
+            }
+        }
+
+        let direction = facing
+            .or_else(|| parent.and_then(|parent| facings.get(parent.get()).ok()))
+            .map(|facing| facing.direction)
+            .unwrap_or_default();
+        let state = controller.state();
+
+        if controller.playing == Some((state, direction)) && !controller.restart {
+            continue;
+        }
+
+        let Some(clip) = controller.clips.get(&state) else {
+            // States without a clip show nothing, e.g. the attack sprite
+            // between swings.
+            animator.stop_anim();
+            controller.playing = Some((state, direction));
+            continue;
+        };
+        let Some(sheet) = sheets.get(sheet_handle) else {
+            continue;
+        };
+
+        match resolve_directional(sheet, &clip.tag, direction) {
+            Some((handle, flip)) => {
+                if controller.restart && animator.is_cur_anim(handle) {
+                    animator.restart_anim();
+                } else {
+                    animator.set_anim(handle);
+                }
+                sprite.flip_x = flip;
+            }
+            None => {
+                if controller.missing.insert(state) {
+                    error!(
+                        "No animation tag '{}' (or a directional variant) for {:?} in spritesheet {:?}",
+                        clip.tag, state, sheet_handle.path(),
+                    );
+                }
+            }
+        }
+
+        controller.playing = Some((state, direction));
+        controller.restart = false;
+    }
+}
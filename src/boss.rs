@@ -0,0 +1,542 @@
+use bevy::{
+    ecs::system::{EntityCommands, SystemParam},
+    prelude::*,
+    utils::HashMap,
+};
+use bevy_entitiles::ldtk::{
+    json::{field::FieldInstance, level::EntityInstance},
+    resources::LdtkAssets,
+};
+use bevy_entitiles_derive::LdtkEntity;
+use bevy_xpbd_2d::prelude::*;
+use bevy_yarnspinner::prelude::*;
+use serde::Deserialize;
+use crate::actor::{EnemyHit, Player, PlayerHit};
+use crate::animation::{AnimState, AnimationController};
+use crate::corpse::{scatter_items, CorpseConfig};
+use crate::facing::Facing;
+use crate::loot::LootRoller;
+use crate::mercy::start_node;
+use crate::movement::{Knockback, MovementController, MovementSet};
+use crate::projectile::{projectile_bundle, Projectile, ProjectileSet, ProjectileTarget};
+use crate::rng::GameRng;
+use crate::ron_asset::{RonAsset, RonAssetPlugin, RonAssets, RonHandle};
+use crate::util::*;
+
+const BOSS_SIZE: f32 = 20.;
+const DOOR_SIZE: f32 = 16.;
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(RonAssetPlugin::<BossSet>::default())
+            .init_resource::<BossConfig>()
+            .register_type::<BossConfig>()
+            .add_event::<BossPhaseChanged>()
+            .add_event::<BossDefeated>()
+            .add_systems(Update, (
+                init_bosses,
+                start_fights,
+                boss_hits,
+                boss_phases,
+                boss_contact,
+                boss_defeated,
+            ).chain())
+            .add_systems(Update, boss_attacks.in_set(MovementSet::Steer).before(ProjectileSet));
+    }
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct BossConfig {
+    /// Bosses only take this share of the knockback of a hit.
+    pub knockback_taken: f32,
+    /// The boss's touch hurts the player within this distance, at most once
+    /// every `contact_cooldown` seconds.
+    pub contact_range: f32,
+    pub contact_cooldown: f32,
+    pub contact_knockback: f32,
+    /// Seconds before a boss projectile that hit nothing fizzles out.
+    pub projectile_lifetime: f32,
+    pub projectile_knockback: f32,
+}
+
+impl Default for BossConfig {
+    fn default() -> Self {
+        Self {
+            knockback_taken: 0.25,
+            contact_range: 16.,
+            contact_cooldown: 1.,
+            contact_knockback: 120.,
+            projectile_lifetime: 3.,
+            projectile_knockback: 60.,
+        }
+    }
+}
+
+/// Every boss of the game by name. Loaded from a `.boss.ron` file, LDtk
+/// `Boss` entities refer to them by name.
+#[derive(Asset, TypePath, Deserialize, Default)]
+pub struct BossSet {
+    pub bosses: HashMap<String, BossDef>,
+}
+
+impl RonAsset for BossSet {
+    const PATH: &'static str = "bosses/bosses.boss.ron";
+    const EXTENSIONS: &'static [&'static str] = &["boss.ron"];
+}
+
+#[derive(Deserialize, Clone)]
+pub struct BossDef {
+    /// Shown above the HP bar.
+    pub name: String,
+    pub hp: f32,
+    /// The fight starts once the player comes this close, and every open
+    /// door this close to the boss shuts until it is over.
+    pub arena_radius: f32,
+    #[serde(default)]
+    pub contact_damage: f32,
+    /// Name of the loot table rolled when the boss is beaten.
+    #[serde(default)]
+    pub reward: String,
+    /// Added to the player's `compleation` when the boss is beaten.
+    #[serde(default)]
+    pub compleation: f32,
+    /// In order of falling HP, the first one starts with the fight.
+    pub phases: Vec<BossPhase>,
+    /// Yarn node played when the boss is beaten.
+    #[serde(default)]
+    pub defeat_dialogue: Option<String>,
+}
+
+impl BossDef {
+    /// Index of the phase a boss with `hp` left should be in.
+    pub fn phase_at(&self, hp: f32) -> usize {
+        let fraction = hp / self.hp;
+        self.phases.iter().rposition(|phase| fraction <= phase.below).unwrap_or(0)
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct BossPhase {
+    /// The phase starts once HP drops to this share of the maximum.
+    pub below: f32,
+    pub speed: f32,
+    pub pattern: AttackPattern,
+    /// Yarn node played when the phase starts.
+    #[serde(default)]
+    pub dialogue: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum AttackPattern {
+    /// Walks at the player, only hurting on contact.
+    Chase,
+    /// Keeps chasing, firing `count` projectiles fanned over `spread`
+    /// radians every `interval` seconds.
+    Volley { count: u32, spread: f32, interval: f32, speed: f32, damage: f32 },
+    /// Every `interval` seconds stands still for `windup` seconds, then
+    /// dashes at where the player was for `duration` seconds.
+    Charge { speed: f32, windup: f32, duration: f32, interval: f32 },
+}
+
+impl AttackPattern {
+    fn interval(&self) -> f32 {
+        match self {
+            AttackPattern::Chase => 0.,
+            AttackPattern::Volley { interval, .. } | AttackPattern::Charge { interval, .. } => *interval,
+        }
+    }
+}
+
+pub type BossDefs = RonHandle<BossSet>;
+
+/// Looks boss definitions up by name once they are loaded.
+#[derive(SystemParam)]
+pub struct Bosses<'w> {
+    set: RonAssets<'w, BossSet>,
+}
+
+impl Bosses<'_> {
+    pub fn is_loaded(&self) -> bool {
+        self.set.is_loaded()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&BossDef> {
+        self.set.get().and_then(|set| set.bosses.get(name))
+    }
+}
+
+/// An LDtk `Boss`. What the fight is like comes from the `BossDef` named
+/// `def`.
+#[derive(Component, LdtkEntity, Default, Reflect)]
+#[spawn_sprite]
+#[callback(boss_spawn)]
+pub struct Boss {
+    #[ldtk_name = "boss"]
+    pub def: String,
+    #[ldtk_default]
+    pub sprite: String,
+}
+
+fn boss_spawn(
+    commands: &mut EntityCommands,
+    _entity_instance: &EntityInstance,
+    _fields: &HashMap<String, FieldInstance>,
+    _asset_server: &AssetServer,
+    _ldtk_assets: &LdtkAssets,
+) {
+    commands.insert(boss_bundle());
+}
+
+/// Physics components every boss needs on top of the LDtk `Boss` data.
+/// Bosses can be hit like enemies, but aren't `Enemy`s, so the enemy AI,
+/// morale and mercy leave them alone. They are optional: a level counts as
+/// cleared, for runs and endings, with its boss still alive, and beating one
+/// counts as a kill.
+pub fn boss_bundle() -> impl Bundle {
+    (
+        EnemyHitBox,
+        Collider::rectangle(BOSS_SIZE, BOSS_SIZE),
+        RigidBody::Dynamic,
+        LockedAxes::ROTATION_LOCKED,
+        Friction {
+            dynamic_coefficient: 0.5,
+            static_coefficient: 0.5,
+            ..Default::default()
+        },
+        Mass(1000.),
+        MovementController::default(),
+        Knockback::default(),
+        Facing::default(),
+        Name::new("Boss"),
+    )
+}
+
+/// How the fight against a boss is going. Added once its `BossDef` is
+/// loaded.
+#[derive(Component)]
+pub struct BossState {
+    pub def: BossDef,
+    pub hp: f32,
+    pub phase: usize,
+    /// Doors the boss shut when the fight started, opened again once it is
+    /// beaten.
+    locked: Vec<Entity>,
+    attack: Timer,
+    charge: Option<Charge>,
+    contact: Timer,
+}
+
+struct Charge {
+    direction: Vec2,
+    speed: f32,
+    windup: Timer,
+    dash: Timer,
+}
+
+impl BossState {
+    pub fn new(def: BossDef) -> Self {
+        let mut state = Self {
+            hp: def.hp,
+            def,
+            phase: 0,
+            locked: Vec::new(),
+            attack: Timer::default(),
+            charge: None,
+            contact: Timer::from_seconds(0., TimerMode::Once),
+        };
+        state.enter_phase(0);
+        state
+    }
+
+    pub fn current_phase(&self) -> Option<&BossPhase> {
+        self.def.phases.get(self.phase)
+    }
+
+    pub fn hp_fraction(&self) -> f32 {
+        (self.hp / self.def.hp).clamp(0., 1.)
+    }
+
+    fn enter_phase(&mut self, phase: usize) {
+        self.phase = phase;
+        self.charge = None;
+
+        let interval = self.current_phase().map_or(0., |phase| phase.pattern.interval());
+        self.attack = Timer::from_seconds(interval, TimerMode::Repeating);
+    }
+}
+
+/// The player came close enough for the fight to start.
+#[derive(Component)]
+pub struct Engaged;
+
+#[derive(Event)]
+pub struct BossPhaseChanged {
+    pub boss: Entity,
+    pub phase: usize,
+}
+
+#[derive(Event)]
+pub struct BossDefeated {
+    pub boss: Entity,
+    pub name: String,
+    pub position: Vec2,
+}
+
+fn init_bosses(
+    mut commands: Commands,
+    bosses_q: Query<(Entity, &Boss), Without<BossState>>,
+    bosses: Bosses,
+) {
+    if !bosses.is_loaded() {
+        return;
+    }
+
+    for (entity, boss) in bosses_q.iter() {
+        let Some(def) = bosses.get(&boss.def) else {
+            warn!("No boss named '{}'", boss.def);
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+
+        commands.entity(entity).insert(BossState::new(def.clone()));
+    }
+}
+
+// The fight starts when the player walks into the arena, which shuts every
+// open door around it. Doors have no collider of their own, the locked ones
+// get one for as long as the fight lasts.
+fn start_fights(
+    mut commands: Commands,
+    mut bosses_q: Query<(Entity, &mut BossState, &GlobalTransform), Without<Engaged>>,
+    mut doors: Query<(Entity, &mut Door, &GlobalTransform)>,
+    player_q: Query<&GlobalTransform, With<PlayerMover>>,
+    mut dialogue_runner: Query<&mut DialogueRunner>,
+) {
+    let Ok(player_t) = player_q.get_single() else {
+        return;
+    };
+    let player_position = player_t.translation().truncate();
+
+    for (entity, mut state, transform) in bosses_q.iter_mut() {
+        let position = transform.translation().truncate();
+        let radius = state.def.arena_radius;
+        if position.distance(player_position) > radius {
+            continue;
+        }
+
+        for (door_entity, mut door, door_t) in doors.iter_mut() {
+            if door.state == DoorState::Open && door_t.translation().truncate().distance(position) <= radius {
+                door.state = DoorState::Closed;
+                state.locked.push(door_entity);
+                commands.entity(door_entity).insert((RigidBody::Static, Collider::rectangle(DOOR_SIZE, DOOR_SIZE)));
+            }
+        }
+
+        if let Some(node) = state.current_phase().and_then(|phase| phase.dialogue.as_deref()) {
+            start_node(&mut dialogue_runner, node);
+        }
+        commands.entity(entity).insert(Engaged);
+    }
+}
+
+// Bosses are hit by the same attacks as enemies, but barely budge.
+fn boss_hits(
+    mut events: EventReader<EnemyHit>,
+    mut bosses_q: Query<(&mut BossState, &mut Knockback, Option<&Children>)>,
+    mut anims: Query<&mut AnimationController>,
+    config: Res<BossConfig>,
+) {
+    for event in events.read() {
+        let Ok((mut state, mut knockback, children)) = bosses_q.get_mut(event.enemy) else {
+            continue;
+        };
+
+        state.hp -= event.damage;
+        knockback.apply(event.direction * event.knockback * config.knockback_taken);
+
+        for child in children.into_iter().flatten() {
+            if let Ok(mut anim) = anims.get_mut(*child) {
+                anim.trigger(AnimState::Hit);
+            }
+        }
+    }
+}
+
+// Phases only ever move forward. A big enough hit skips straight past the
+// ones in between.
+fn boss_phases(
+    mut bosses_q: Query<(Entity, &mut BossState)>,
+    mut changes: EventWriter<BossPhaseChanged>,
+    mut dialogue_runner: Query<&mut DialogueRunner>,
+) {
+    for (entity, mut state) in bosses_q.iter_mut() {
+        if state.hp <= 0. {
+            continue;
+        }
+        let phase = state.def.phase_at(state.hp);
+        if phase <= state.phase {
+            continue;
+        }
+
+        state.enter_phase(phase);
+
+        if let Some(node) = state.current_phase().and_then(|phase| phase.dialogue.as_deref()) {
+            start_node(&mut dialogue_runner, node);
+        }
+        changes.send(BossPhaseChanged { boss: entity, phase });
+    }
+}
+
+fn boss_attacks(
+    mut commands: Commands,
+    mut bosses_q: Query<(Entity, &mut BossState, &GlobalTransform, &mut MovementController, Option<&Children>), With<Engaged>>,
+    mut anims: Query<&mut AnimationController>,
+    player_q: Query<&GlobalTransform, With<PlayerMover>>,
+    config: Res<BossConfig>,
+    time: Res<Time>,
+) {
+    let player = player_q.get_single().ok().map(|transform| transform.translation().truncate());
+
+    for (entity, mut state, transform, mut controller, children) in bosses_q.iter_mut() {
+        let (Some(player), Some(phase)) = (player, state.current_phase()) else {
+            controller.input = Vec2::ZERO;
+            continue;
+        };
+        let (speed, pattern) = (phase.speed, phase.pattern);
+        let position = transform.translation().truncate();
+        let to_player = (player - position).normalize_or_zero();
+
+        controller.input = to_player;
+        controller.max_speed = Some(speed);
+
+        if let Some(charge) = state.charge.as_mut() {
+            if !charge.windup.tick(time.delta()).finished() {
+                controller.input = Vec2::ZERO;
+            } else if !charge.dash.tick(time.delta()).finished() {
+                controller.input = charge.direction;
+                controller.max_speed = Some(charge.speed);
+            } else {
+                state.charge = None;
+            }
+        } else if pattern != AttackPattern::Chase && state.attack.tick(time.delta()).just_finished() {
+            match pattern {
+                AttackPattern::Chase => {}
+                AttackPattern::Volley { count, spread, speed, damage, .. } => {
+                    for index in 0..count {
+                        let angle = match count {
+                            1 => 0.,
+                            _ => spread * (index as f32 / (count - 1) as f32 - 0.5),
+                        };
+                        let direction = Vec2::from_angle(angle).rotate(to_player);
+                        let projectile = Projectile::new(
+                            entity,
+                            ProjectileTarget::Player,
+                            damage,
+                            config.projectile_knockback,
+                            config.projectile_lifetime,
+                        );
+
+                        commands.spawn(projectile_bundle(projectile, position, BOSS_SIZE / 2., direction * speed));
+                    }
+                }
+                AttackPattern::Charge { speed, windup, duration, .. } => {
+                    state.charge = Some(Charge {
+                        direction: to_player,
+                        speed,
+                        windup: Timer::from_seconds(windup, TimerMode::Once),
+                        dash: Timer::from_seconds(duration, TimerMode::Once),
+                    });
+                    controller.input = Vec2::ZERO;
+                }
+            }
+        }
+
+        for child in children.into_iter().flatten() {
+            if let Ok(mut anim) = anims.get_mut(*child) {
+                anim.set_base(if controller.input == Vec2::ZERO { AnimState::Idle } else { AnimState::Walk });
+            }
+        }
+    }
+}
+
+fn boss_contact(
+    mut bosses_q: Query<(Entity, &mut BossState, &GlobalTransform), With<Engaged>>,
+    player_q: Query<&GlobalTransform, With<PlayerMover>>,
+    mut hits: EventWriter<PlayerHit>,
+    config: Res<BossConfig>,
+    time: Res<Time>,
+) {
+    let Ok(player_t) = player_q.get_single() else {
+        return;
+    };
+    let player_position = player_t.translation().truncate();
+
+    for (entity, mut state, transform) in bosses_q.iter_mut() {
+        state.contact.tick(time.delta());
+
+        if state.def.contact_damage <= 0. || !state.contact.finished() {
+            continue;
+        }
+        let away = player_position - transform.translation().truncate();
+        if away.length() > config.contact_range {
+            continue;
+        }
+
+        hits.send(PlayerHit {
+            attacker: entity,
+            damage: state.def.contact_damage,
+            direction: away.normalize_or_zero(),
+            knockback: config.contact_knockback,
+        });
+        state.contact = Timer::from_seconds(config.contact_cooldown, TimerMode::Once);
+    }
+}
+
+fn boss_defeated(
+    mut commands: Commands,
+    bosses_q: Query<(Entity, &BossState, &GlobalTransform)>,
+    mut doors: Query<&mut Door>,
+    mut player_q: Query<&mut Player>,
+    mut dialogue_runner: Query<&mut DialogueRunner>,
+    mut defeats: EventWriter<BossDefeated>,
+    loot: LootRoller,
+    corpse_config: Res<CorpseConfig>,
+    mut rng: ResMut<GameRng>,
+) {
+    for (entity, state, transform) in bosses_q.iter() {
+        if state.hp > 0. {
+            continue;
+        }
+        let position = transform.translation().truncate();
+
+        if !state.def.reward.is_empty() {
+            let drops = loot.roll(&state.def.reward, &mut rng);
+            scatter_items(&mut commands, &mut rng, &corpse_config, &drops, position);
+        }
+
+        for door_entity in &state.locked {
+            if let Ok(mut door) = doors.get_mut(*door_entity) {
+                door.state = DoorState::Open;
+                commands.entity(*door_entity).remove::<(RigidBody, Collider)>();
+            }
+        }
+
+        if let Ok(mut player) = player_q.get_single_mut() {
+            player.compleation += state.def.compleation;
+            player.killed += 1;
+        }
+        if let Some(node) = &state.def.defeat_dialogue {
+            start_node(&mut dialogue_runner, node);
+        }
+
+        defeats.send(BossDefeated {
+            boss: entity,
+            name: state.def.name.clone(),
+            position,
+        });
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+use crate::boss::{BossState, Engaged};
+
+const BAR_BACKGROUND_COLOR: Color = Color::rgba(0.1, 0.1, 0.15, 0.9);
+const BAR_COLOR: Color = Color::rgb(0.7, 0.15, 0.2);
+
+/// Name and HP bar of the boss being fought.
+pub struct BossUiPlugin;
+
+impl Plugin for BossUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_boss_bar)
+            .add_systems(Update, update_boss_bar);
+    }
+}
+
+#[derive(Component)]
+struct BossBar;
+
+#[derive(Component)]
+struct BossName;
+
+#[derive(Component)]
+struct BossHealth;
+
+fn spawn_boss_bar(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(24.),
+                    left: Val::Percent(25.),
+                    width: Val::Percent(50.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(4.),
+                    ..Default::default()
+                },
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+            BossBar,
+        ))
+        .with_children(|bar| {
+            bar.spawn((
+                TextBundle::from_section("", TextStyle {
+                    font_size: 16.,
+                    ..Default::default()
+                }),
+                BossName,
+            ));
+
+            bar.spawn(NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Px(8.),
+                    ..Default::default()
+                },
+                background_color: BAR_BACKGROUND_COLOR.into(),
+                ..Default::default()
+            })
+            .with_children(|background| {
+                background.spawn((
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Percent(100.),
+                            height: Val::Percent(100.),
+                            ..Default::default()
+                        },
+                        background_color: BAR_COLOR.into(),
+                        ..Default::default()
+                    },
+                    BossHealth,
+                ));
+            });
+        });
+}
+
+// Only shown while a fight is on.
+fn update_boss_bar(
+    bosses_q: Query<&BossState, With<Engaged>>,
+    mut bar_q: Query<&mut Visibility, With<BossBar>>,
+    mut name_q: Query<&mut Text, With<BossName>>,
+    mut health_q: Query<&mut Style, With<BossHealth>>,
+) {
+    let Ok(mut visibility) = bar_q.get_single_mut() else {
+        return;
+    };
+    let Some(state) = bosses_q.iter().next() else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+
+    *visibility = Visibility::Visible;
+
+    if let Ok(mut text) = name_q.get_single_mut() {
+        if text.sections[0].value != state.def.name {
+            text.sections[0].value = state.def.name.clone();
+        }
+    }
+    if let Ok(mut style) = health_q.get_single_mut() {
+        style.width = Val::Percent(state.hp_fraction() * 100.);
+    }
+}
@@ -0,0 +1,115 @@
+use std::ops::Range;
+use bevy::{ecs::entity::EntityHashSet, prelude::*};
+use bevy_xpbd_2d::prelude::*;
+use crate::actor::{Dead, EnemyHit, Player};
+use crate::shop::shop_closed;
+use crate::util::*;
+
+/// How long a swing and the attack animation playing it last.
+pub const SWING_DURATION: f32 = 0.5;
+// Part of the attack animation where the blade is actually out, the only
+// frames that hurt.
+const ACTIVE_FRAMES: Range<f32> = 0.2..0.7;
+const KNOCKBACK_PER_SKILL: f32 = 60.;
+
+pub struct CombatPlugin;
+
+impl Plugin for CombatPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                start_swing.run_if(shop_closed),
+                melee_hits,
+            ).chain(),
+        );
+    }
+}
+
+/// A swing in progress on an attack box. Remembers who it already hit so
+/// every enemy takes damage at most once per swing.
+#[derive(Component)]
+pub struct MeleeSwing {
+    timer: Timer,
+    hit: EntityHashSet,
+}
+
+impl MeleeSwing {
+    pub fn new() -> Self {
+        Self {
+            timer: Timer::from_seconds(SWING_DURATION, TimerMode::Once),
+            hit: EntityHashSet::default(),
+        }
+    }
+
+    pub fn active(&self) -> bool {
+        ACTIVE_FRAMES.contains(&self.timer.fraction())
+    }
+}
+
+impl Default for MeleeSwing {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn start_swing(
+    mut commands: Commands,
+    buttons: Res<ButtonInput<MouseButton>>,
+    attack_q: Query<Entity, With<PlayerAttackBox>>,
+) {
+    if !buttons.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    for attack_box in attack_q.iter() {
+        commands.entity(attack_box).insert(MeleeSwing::new());
+    }
+}
+
+fn melee_hits(
+    mut commands: Commands,
+    mut swings: Query<(Entity, &mut MeleeSwing, &Collider, &GlobalTransform)>,
+    enemies: Query<&GlobalTransform, (With<EnemyHitBox>, Without<Dead>)>,
+    mover_q: Query<Entity, With<PlayerMover>>,
+    players: Query<&Player>,
+    spatial_query: SpatialQuery,
+    mut events: EventWriter<EnemyHit>,
+    time: Res<Time>,
+) {
+    let sword_skill = players.get_single().map_or(1., |player| player.sword_skill);
+
+    for (entity, mut swing, collider, transform) in swings.iter_mut() {
+        swing.timer.tick(time.delta());
+
+        if swing.timer.finished() {
+            commands.entity(entity).remove::<MeleeSwing>();
+            continue;
+        }
+        if !swing.active() {
+            continue;
+        }
+
+        let attacker = mover_q.get_single().unwrap_or(entity);
+        let (_, rotation, position) = transform.to_scale_rotation_translation();
+        let angle = rotation.to_euler(EulerRot::XYZ).2;
+        let filter = SpatialQueryFilter::default().with_excluded_entities([entity, attacker]);
+
+        for enemy in spatial_query.shape_intersections(collider, position.truncate(), angle, filter) {
+            let Ok(enemy_t) = enemies.get(enemy) else {
+                continue;
+            };
+            if !swing.hit.insert(enemy) {
+                continue;
+            }
+
+            events.send(EnemyHit {
+                enemy,
+                attacker,
+                damage: sword_skill,
+                direction: (enemy_t.translation() - position).truncate().normalize_or_zero(),
+                knockback: sword_skill * KNOCKBACK_PER_SKILL,
+            });
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_xpbd_2d::prelude::*;
+use crate::actor::{Dead, Enemy};
+use crate::loot::{add_stack, LootRoller};
+use crate::rng::GameRng;
+use crate::util::*;
+
+pub struct CorpsePlugin;
+
+impl Plugin for CorpsePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CorpseConfig>()
+            .register_type::<CorpseConfig>()
+            .add_systems(Update, (
+                enemy_died,
+                despawn_corpses,
+            ));
+    }
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct CorpseConfig {
+    /// Seconds a corpse stays around, `None` keeps it forever.
+    pub despawn_after: Option<f32>,
+    /// Dropped loot lands somewhere between these distances from the corpse
+    /// or chest it came out of.
+    pub scatter_min: f32,
+    pub scatter_max: f32,
+}
+
+impl Default for CorpseConfig {
+    fn default() -> Self {
+        Self {
+            despawn_after: Some(10.),
+            scatter_min: 6.,
+            scatter_max: 14.,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct CorpseTimer(pub Timer);
+
+/// Marks items that were dropped at runtime rather than placed in LDtk, so
+/// the view knows to give them a sprite.
+#[derive(Component)]
+pub struct DroppedItem;
+
+fn enemy_died(
+    mut commands: Commands,
+    dead_q: Query<(Entity, &Enemy, &GlobalTransform), Added<Dead>>,
+    config: Res<CorpseConfig>,
+    loot: LootRoller,
+    mut rng: ResMut<GameRng>,
+) {
+    for (entity, enemy, transform) in dead_q.iter() {
+        // Corpses don't block anyone and can't be hit again.
+        commands.entity(entity).remove::<(Collider, EnemyHitBox)>();
+
+        if let Some(seconds) = config.despawn_after {
+            commands.entity(entity).insert(CorpseTimer(Timer::from_seconds(seconds, TimerMode::Once)));
+        }
+
+        let mut drops = stack_items(&enemy.inventory.0);
+        if !enemy.loot.is_empty() {
+            for (itype, count) in loot.roll(&enemy.loot, &mut rng) {
+                add_stack(&mut drops, itype, count);
+            }
+        }
+
+        scatter_items(&mut commands, &mut rng, &config, &drops, transform.translation().truncate());
+    }
+}
+
+/// Drops every stack at a random spot around `position`.
+pub fn scatter_items(
+    commands: &mut Commands,
+    rng: &mut GameRng,
+    config: &CorpseConfig,
+    items: &[(ItemType, i32)],
+    position: Vec2,
+) {
+    for (itype, count) in items {
+        let angle = rng.range_f32(0., TAU);
+        let distance = rng.range_f32(config.scatter_min, config.scatter_max);
+
+        spawn_dropped_item(commands, *itype, *count, position + Vec2::from_angle(angle) * distance);
+    }
+}
+
+pub fn spawn_dropped_item(commands: &mut Commands, itype: ItemType, count: i32, position: Vec2) -> Entity {
+    commands
+        .spawn((
+            Item { itype, count },
+            DroppedItem,
+            Loot,
+            SpatialBundle::from_transform(Transform::from_translation(position.extend(1.))),
+            Name::new("Item"),
+        ))
+        .id()
+}
+
+// Collapses e.g. [Coins, Coins, Key] into [(Coins, 2), (Key, 1)], keeping
+// the order items first appear in.
+fn stack_items(items: &[ItemType]) -> Vec<(ItemType, i32)> {
+    let mut stacks = Vec::new();
+
+    for item in items {
+        add_stack(&mut stacks, *item, 1);
+    }
+
+    stacks
+}
+
+fn despawn_corpses(
+    mut commands: Commands,
+    mut corpses: Query<(Entity, &mut CorpseTimer)>,
+    time: Res<Time>,
+) {
+    for (entity, mut timer) in corpses.iter_mut() {
+        timer.0.tick(time.delta());
+
+        if timer.0.just_finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use bevy::prelude::*;
+use crate::ending::{EndingPhase, EndingSequence};
+use crate::profile::Profile;
+
+/// Dims the game behind full screen panels, like the credits.
+pub const BACKGROUND_COLOR: Color = Color::rgba(0., 0., 0., 0.85);
+const SCROLL_SPEED: f32 = 30.;
+
+const CREDITS: &[&str] = &[
+    "RETURNER",
+    "",
+    "Made for Acerola Jam 0",
+    "",
+    "Built with Bevy, bevy_xpbd, bevy_entitiles,",
+    "seldom_state and Yarn Spinner",
+    "",
+    "Thank you for playing",
+];
+
+/// Rolls the credits once the ending dialogue is over.
+pub struct CreditsPlugin;
+
+impl Plugin for CreditsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (spawn_credits, scroll_credits));
+    }
+}
+
+// Starts just below the screen and moves up by `offset` pixels.
+#[derive(Component, Default)]
+struct Credits {
+    offset: f32,
+}
+
+fn spawn_credits(
+    mut commands: Commands,
+    sequence: Option<Res<EndingSequence>>,
+    profile: Res<Profile>,
+    credits_q: Query<(), With<Credits>>,
+) {
+    let Some(sequence) = sequence else {
+        return;
+    };
+    if sequence.phase != EndingPhase::Credits || !credits_q.is_empty() {
+        return;
+    }
+
+    let stats = &sequence.stats;
+    let summary = [
+        format!("The {} ending", sequence.ending.name()),
+        format!("Killed {}, spared {}", stats.kills, stats.spares),
+        format!("Collected {} items in {:.0}s", stats.items, stats.time),
+        format!("Endings found: {}", profile.endings.len()),
+        String::new(),
+    ];
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                overflow: Overflow::clip(),
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            background_color: BACKGROUND_COLOR.into(),
+            ..Default::default()
+        })
+        .with_children(|screen| {
+            screen
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            top: Val::Percent(100.),
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            row_gap: Val::Px(6.),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    Credits::default(),
+                ))
+                .with_children(|column| {
+                    let lines = summary.into_iter().chain(CREDITS.iter().map(|line| line.to_string()));
+                    for line in lines {
+                        column.spawn(TextBundle::from_section(line, TextStyle {
+                            font_size: 20.,
+                            ..Default::default()
+                        }));
+                    }
+                });
+        });
+}
+
+fn scroll_credits(mut credits_q: Query<(&mut Credits, &mut Style)>, time: Res<Time>) {
+    for (mut credits, mut style) in credits_q.iter_mut() {
+        credits.offset += SCROLL_SPEED * time.delta_seconds();
+        style.margin.top = Val::Px(-credits.offset);
+    }
+}
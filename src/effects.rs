@@ -0,0 +1,138 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use crate::actor::{Dead, Enemy};
+use crate::corpse::CorpseTimer;
+use crate::mercy::EnemySpared;
+use crate::pickup::ItemPickedUp;
+use crate::view::ItemIcons;
+
+const DEATH_PARTICLES: usize = 12;
+const PARTICLE_LIFETIME: f32 = 0.6;
+const DISSOLVE_TIME: f32 = 1.5;
+const PICKUP_RISE_SPEED: f32 = 24.;
+const PICKUP_LIFETIME: f32 = 0.4;
+
+pub struct EffectsPlugin;
+
+impl Plugin for EffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (
+            death_burst,
+            spared_burst,
+            update_particles,
+            dissolve_corpses,
+            pickup_feedback,
+        ));
+    }
+}
+
+#[derive(Component)]
+struct Particle {
+    velocity: Vec2,
+    lifetime: Timer,
+}
+
+fn death_burst(
+    mut commands: Commands,
+    dead_q: Query<&GlobalTransform, (With<Enemy>, Added<Dead>)>,
+) {
+    for transform in dead_q.iter() {
+        burst(&mut commands, transform.translation().truncate(), Color::rgb(0.6, 0.1, 0.1));
+    }
+}
+
+// Spared enemies vanish in a pale puff instead of a corpse.
+fn spared_burst(mut commands: Commands, mut spared: EventReader<EnemySpared>) {
+    for event in spared.read() {
+        burst(&mut commands, event.position, Color::rgb(0.9, 0.9, 0.8));
+    }
+}
+
+fn burst(commands: &mut Commands, origin: Vec2, color: Color) {
+    // A ring with alternating speeds. Purely cosmetic, so it stays off the
+    // gameplay RNG and doesn't change replays.
+    for i in 0..DEATH_PARTICLES {
+        let direction = Vec2::from_angle(i as f32 / DEATH_PARTICLES as f32 * TAU);
+        let speed = if i % 2 == 0 { 30. } else { 15. };
+
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(Vec2::splat(1.)),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(origin.extend(5.)),
+                ..Default::default()
+            },
+            Particle {
+                velocity: direction * speed,
+                lifetime: Timer::from_seconds(PARTICLE_LIFETIME, TimerMode::Once),
+            },
+        ));
+    }
+}
+
+fn update_particles(
+    mut commands: Commands,
+    mut particles: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+    time: Res<Time>,
+) {
+    for (entity, mut particle, mut transform, mut sprite) in particles.iter_mut() {
+        particle.lifetime.tick(time.delta());
+
+        if particle.lifetime.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        transform.translation += (particle.velocity * time.delta_seconds()).extend(0.);
+        particle.velocity *= 0.9;
+        sprite.color.set_a(particle.lifetime.fraction_remaining());
+    }
+}
+
+// Fade the corpse out over the last moments before it is despawned.
+fn dissolve_corpses(
+    corpses: Query<(&CorpseTimer, &Children)>,
+    mut sprites: Query<&mut Sprite>,
+) {
+    for (timer, children) in corpses.iter() {
+        let alpha = (timer.0.remaining_secs() / DISSOLVE_TIME).clamp(0., 1.);
+
+        for child in children {
+            if let Ok(mut sprite) = sprites.get_mut(*child) {
+                sprite.color.set_a(alpha);
+            }
+        }
+    }
+}
+
+// A blip and the item's icon floating up from where it was picked up.
+fn pickup_feedback(
+    mut commands: Commands,
+    mut picked_up: EventReader<ItemPickedUp>,
+    icons: Res<ItemIcons>,
+    assets: Res<AssetServer>,
+) {
+    for event in picked_up.read() {
+        commands.spawn(AudioBundle {
+            source: assets.load("sounds/pickup.wav"),
+            settings: PlaybackSettings::DESPAWN,
+        });
+
+        commands.spawn((
+            SpriteSheetBundle {
+                texture: icons.texture.clone(),
+                atlas: icons.atlas(event.itype),
+                transform: Transform::from_translation(event.position.extend(5.)),
+                ..Default::default()
+            },
+            Particle {
+                velocity: Vec2::Y * PICKUP_RISE_SPEED,
+                lifetime: Timer::from_seconds(PICKUP_LIFETIME, TimerMode::Once),
+            },
+        ));
+    }
+}
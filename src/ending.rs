@@ -0,0 +1,221 @@
+use bevy::{app::AppExit, prelude::*};
+use bevy_yarnspinner::prelude::*;
+use crate::actor::{Dead, Enemy, Player};
+use crate::mercy::start_node;
+use crate::pickup::ItemPickedUp;
+use crate::profile::Profile;
+use crate::spawner::SpawnerState;
+
+pub struct EndingPlugin;
+
+impl Plugin for EndingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EndingConfig>()
+            .register_type::<EndingConfig>()
+            .init_resource::<RunStats>()
+            .add_systems(Update, track_run_stats)
+            // Deaths and spares despawn at the end of `Update`, so by now the
+            // enemy count is final for the frame.
+            .add_systems(PostUpdate, (
+                check_for_ending,
+                play_ending,
+            ).chain().in_set(EndingSet));
+    }
+}
+
+/// Checks for and plays the ending, in `PostUpdate`.
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct EndingSet;
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct EndingConfig {
+    /// Finishing faster than this, in seconds, is the swift ending.
+    pub swift_time: f32,
+    /// Picking up at least this many items is the hoarder ending.
+    pub hoarder_items: i32,
+    /// Seconds the ending dialogue gets before the credits roll.
+    pub dialogue_time: f32,
+    pub credits_time: f32,
+}
+
+impl Default for EndingConfig {
+    fn default() -> Self {
+        Self {
+            swift_time: 180.,
+            hoarder_items: 50,
+            dialogue_time: 10.,
+            credits_time: 20.,
+        }
+    }
+}
+
+/// What the player did this game that isn't already on `Player`.
+#[derive(Resource, Default, Debug)]
+pub struct RunStats {
+    pub items_collected: i32,
+    /// Seconds played, stops counting once an ending starts.
+    pub time: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct EndingStats {
+    pub kills: i32,
+    pub spares: i32,
+    pub items: i32,
+    pub time: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Ending {
+    /// Nobody was killed.
+    Pacifist,
+    /// Killed, but let some go.
+    Merciful,
+    /// Killed everything, quickly.
+    Swift,
+    /// Killed everything and took everything.
+    Hoarder,
+    /// Killed everything.
+    Returner,
+}
+
+impl Ending {
+    /// Picks the first ending the stats qualify for, choices over kills
+    /// count more than speed or loot.
+    pub fn choose(stats: &EndingStats, config: &EndingConfig) -> Self {
+        if stats.kills == 0 {
+            Ending::Pacifist
+        } else if stats.spares > 0 {
+            Ending::Merciful
+        } else if stats.time < config.swift_time {
+            Ending::Swift
+        } else if stats.items >= config.hoarder_items {
+            Ending::Hoarder
+        } else {
+            Ending::Returner
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Ending::Pacifist => "Pacifist",
+            Ending::Merciful => "Merciful",
+            Ending::Swift => "Swift",
+            Ending::Hoarder => "Hoarder",
+            Ending::Returner => "Returner",
+        }
+    }
+
+    pub fn node(&self) -> &'static str {
+        match self {
+            Ending::Pacifist => "PacifistEnd",
+            Ending::Merciful => "MercifulEnd",
+            Ending::Swift => "SwiftEnd",
+            Ending::Hoarder => "HoarderEnd",
+            Ending::Returner => "End",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EndingPhase {
+    Dialogue,
+    Credits,
+}
+
+/// Present once an ending has been reached, until the game exits.
+#[derive(Resource, Debug)]
+pub struct EndingSequence {
+    pub ending: Ending,
+    pub stats: EndingStats,
+    pub phase: EndingPhase,
+    timer: Timer,
+}
+
+fn track_run_stats(
+    mut stats: ResMut<RunStats>,
+    mut picked_up: EventReader<ItemPickedUp>,
+    sequence: Option<Res<EndingSequence>>,
+    time: Res<Time>,
+) {
+    stats.items_collected += picked_up.read().map(|event| event.count).sum::<i32>();
+
+    if sequence.is_none() {
+        stats.time += time.delta_seconds();
+    }
+}
+
+// The game ends at full compleation, or when every enemy was let go and no
+// wave is still coming.
+fn check_for_ending(
+    mut commands: Commands,
+    sequence: Option<Res<EndingSequence>>,
+    player_q: Query<&Player>,
+    enemies: Query<(), (With<Enemy>, Without<Dead>)>,
+    spawners: Query<&SpawnerState>,
+    run: Res<RunStats>,
+    config: Res<EndingConfig>,
+    mut profile: ResMut<Profile>,
+    mut dialogue_runner: Query<&mut DialogueRunner>,
+) {
+    if sequence.is_some() {
+        return;
+    }
+    let Ok(player) = player_q.get_single() else {
+        return;
+    };
+
+    // Bosses can't be spared, so they are left out, see `boss_bundle`.
+    let waves_running = spawners.iter().any(SpawnerState::is_running);
+    let spared_everyone = enemies.is_empty() && !waves_running && player.killed == 0 && player.spared > 0;
+    if player.compleation < 100. && !spared_everyone {
+        return;
+    }
+
+    let stats = EndingStats {
+        kills: player.killed,
+        spares: player.spared,
+        items: run.items_collected,
+        time: run.time,
+    };
+    let ending = Ending::choose(&stats, &config);
+    info!("Reached the {} ending: {:?}", ending.name(), stats);
+
+    start_node(&mut dialogue_runner, ending.node());
+
+    profile.record_ending(ending.name(), stats.time);
+    profile.save();
+
+    commands.insert_resource(EndingSequence {
+        ending,
+        stats,
+        phase: EndingPhase::Dialogue,
+        timer: Timer::from_seconds(config.dialogue_time, TimerMode::Once),
+    });
+}
+
+fn play_ending(
+    sequence: Option<ResMut<EndingSequence>>,
+    config: Res<EndingConfig>,
+    time: Res<Time>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let Some(mut sequence) = sequence else {
+        return;
+    };
+
+    if !sequence.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    match sequence.phase {
+        EndingPhase::Dialogue => {
+            sequence.phase = EndingPhase::Credits;
+            sequence.timer = Timer::from_seconds(config.credits_time, TimerMode::Once);
+        }
+        EndingPhase::Credits => {
+            exit.send(AppExit);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use bevy::prelude::*;
+use bevy_asepritesheet::prelude::*;
+use crate::aim::AimDirection;
+
+const MIN_FACING_MOTION: f32 = 0.05;
+
+pub struct FacingPlugin;
+
+impl Plugin for FacingPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Facing>()
+            .add_systems(Update, (aim_facing, movement_facing));
+    }
+}
+
+/// One of the eight directions a sprite can be authored for. Aseprite tags
+/// for a direction use the suffix returned by `suffix`, e.g. `walk_ne`.
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Direction8 {
+    N,
+    NE,
+    #[default]
+    E,
+    SE,
+    S,
+    SW,
+    W,
+    NW,
+}
+
+impl Direction8 {
+    pub const ALL: [Direction8; 8] = [
+        Direction8::E,
+        Direction8::NE,
+        Direction8::N,
+        Direction8::NW,
+        Direction8::W,
+        Direction8::SW,
+        Direction8::S,
+        Direction8::SE,
+    ];
+
+    pub fn from_vec(direction: Vec2) -> Option<Self> {
+        if direction.length_squared() < f32::EPSILON {
+            return None;
+        }
+
+        let angle = direction.y.atan2(direction.x);
+        let sector = (angle / std::f32::consts::FRAC_PI_4).round() as i32;
+
+        Some(Self::ALL[sector.rem_euclid(8) as usize])
+    }
+
+    /// Unit vector pointing this way.
+    pub fn to_vec(&self) -> Vec2 {
+        let sector = Self::ALL.iter().position(|direction| direction == self).unwrap_or_default();
+        Vec2::from_angle(sector as f32 * std::f32::consts::FRAC_PI_4)
+    }
+
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            Direction8::N => "n",
+            Direction8::NE => "ne",
+            Direction8::E => "e",
+            Direction8::SE => "se",
+            Direction8::S => "s",
+            Direction8::SW => "sw",
+            Direction8::W => "w",
+            Direction8::NW => "nw",
+        }
+    }
+
+    /// The direction reflected across the vertical axis.
+    pub fn mirrored(&self) -> Self {
+        match self {
+            Direction8::NE => Direction8::NW,
+            Direction8::E => Direction8::W,
+            Direction8::SE => Direction8::SW,
+            Direction8::SW => Direction8::SE,
+            Direction8::W => Direction8::E,
+            Direction8::NW => Direction8::NE,
+            other => *other,
+        }
+    }
+
+    pub fn faces_west(&self) -> bool {
+        matches!(self, Direction8::NW | Direction8::W | Direction8::SW)
+    }
+}
+
+/// Which way an actor is looking. Sprites of the actor pick their
+/// directional animation from this.
+#[derive(Component, Reflect, Default)]
+pub struct Facing {
+    pub direction: Direction8,
+    previous: Option<Vec2>,
+}
+
+/// Facing follows the `AimDirection` instead of movement.
+#[derive(Component)]
+pub struct FaceAim;
+
+/// Finds the tag to play for `base` facing `direction`, and whether the
+/// sprite must be flipped. Tries the exact direction first, then its mirror
+/// image with a flip, then the undirected tag flipped when facing west.
+pub fn resolve_directional(
+    sheet: &Spritesheet,
+    base: &str,
+    direction: Direction8,
+) -> Option<(AnimHandle, bool)> {
+    let exists = |name: &str| {
+        let handle = sheet.get_anim_handle(name);
+        sheet.get_anim(&handle).is_ok().then_some(handle)
+    };
+
+    if let Some(handle) = exists(&format!("{}_{}", base, direction.suffix())) {
+        return Some((handle, false));
+    }
+
+    let mirrored = direction.mirrored();
+    if mirrored != direction {
+        if let Some(handle) = exists(&format!("{}_{}", base, mirrored.suffix())) {
+            return Some((handle, true));
+        }
+    }
+
+    exists(base).map(|handle| (handle, direction.faces_west()))
+}
+
+fn aim_facing(mut facing_q: Query<(&mut Facing, &AimDirection), With<FaceAim>>) {
+    for (mut facing, aim) in facing_q.iter_mut() {
+        if let Some(direction) = Direction8::from_vec(aim.0) {
+            facing.direction = direction;
+        }
+    }
+}
+
+fn movement_facing(mut facing_q: Query<(&mut Facing, &GlobalTransform), Without<FaceAim>>) {
+    for (mut facing, transform) in facing_q.iter_mut() {
+        let position = transform.translation().truncate();
+
+        if let Some(previous) = facing.previous {
+            let delta = position - previous;
+
+            // Ignore sub-pixel jitter from the physics solver.
+            if delta.length() > MIN_FACING_MOTION {
+                if let Some(direction) = Direction8::from_vec(delta) {
+                    facing.direction = direction;
+                }
+            }
+        }
+
+        facing.previous = Some(position);
+    }
+}
@@ -0,0 +1,76 @@
+use bevy::prelude::*;
+use crate::shop::shop_closed;
+use crate::util::*;
+
+/// E acts on the nearest thing in reach: picks up an item, opens a chest,
+/// talks to a merchant or spares a beaten enemy. Only ever one of them.
+pub struct InteractPlugin;
+
+impl Plugin for InteractPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NearestInteractable>()
+            .add_event::<Interact>()
+            .add_systems(Update, (
+                find_nearest_interactable,
+                interact.run_if(shop_closed),
+            ).chain().in_set(InteractSet));
+    }
+}
+
+/// Picks the target of E and sends `Interact`, in `Update`. Systems that mark
+/// things `Interactable` run before it, the ones reading `Interact` after.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InteractSet;
+
+/// Something E can be used on from within `range`. The module owning the
+/// thing adds and removes it as it becomes usable.
+#[derive(Component, Clone, Copy)]
+pub struct Interactable {
+    pub range: f32,
+}
+
+/// What E would act on right now, if anything.
+#[derive(Resource, Default)]
+pub struct NearestInteractable(pub Option<Entity>);
+
+/// E was used on `target`.
+#[derive(Event)]
+pub struct Interact {
+    pub target: Entity,
+}
+
+fn find_nearest_interactable(
+    mut nearest: ResMut<NearestInteractable>,
+    interactables: Query<(Entity, &Interactable, &GlobalTransform)>,
+    player_q: Query<&GlobalTransform, With<PlayerMover>>,
+) {
+    let Ok(player_t) = player_q.get_single() else {
+        nearest.0 = None;
+        return;
+    };
+    let player = player_t.translation().truncate();
+
+    nearest.0 = interactables
+        .iter()
+        .map(|(entity, interactable, transform)| {
+            (entity, interactable, transform.translation().truncate().distance(player))
+        })
+        .filter(|(_, interactable, distance)| *distance < interactable.range)
+        .min_by(|(.., a), (.., b)| a.total_cmp(b))
+        .map(|(entity, ..)| entity);
+}
+
+fn interact(
+    input: Res<ButtonInput<KeyCode>>,
+    nearest: Res<NearestInteractable>,
+    mut events: EventWriter<Interact>,
+) {
+    if !input.just_released(KeyCode::KeyE) {
+        return;
+    }
+    let Some(target) = nearest.0 else {
+        return;
+    };
+
+    events.send(Interact { target });
+}
@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+use crate::actor::Player;
+use crate::util::*;
+
+const DEFAULT_CAPACITY: usize = 12;
+
+pub struct InventoryPlugin;
+
+impl Plugin for InventoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Inventory>()
+            .add_systems(Update, init_player_inventory);
+    }
+}
+
+impl ItemType {
+    /// How many of the item fit in one inventory slot.
+    pub fn max_stack(&self) -> i32 {
+        match self {
+            ItemType::Coins => 999,
+            ItemType::Soul => 99,
+            ItemType::Key | ItemType::Scroll | ItemType::Bandage => 10,
+            ItemType::Sword => 1,
+        }
+    }
+
+    pub fn is_equipment(&self) -> bool {
+        matches!(self, ItemType::Sword)
+    }
+}
+
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ItemStack {
+    pub itype: ItemType,
+    pub count: i32,
+}
+
+/// Items grouped into stacks, limited to `capacity` slots, plus a weapon
+/// slot that doesn't count toward the capacity.
+#[derive(Component, Reflect, Clone, Debug)]
+pub struct Inventory {
+    stacks: Vec<ItemStack>,
+    pub capacity: usize,
+    pub equipped: Option<ItemType>,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self {
+            stacks: Vec::new(),
+            capacity: DEFAULT_CAPACITY,
+            equipped: None,
+        }
+    }
+}
+
+impl Inventory {
+    /// Builds an inventory from a flat list like the LDtk `inventory` field,
+    /// equipping the first piece of equipment in it. Items that don't fit
+    /// are left out with a warning.
+    pub fn from_items(items: &[ItemType]) -> Self {
+        let mut inventory = Self::default();
+
+        for item in items {
+            if inventory.add(*item, 1) > 0 {
+                warn!("No room for a starting {:?} in an inventory of {} slots", item, inventory.capacity);
+            }
+        }
+
+        inventory
+    }
+
+    /// The inventory as a flat list, the inverse of `from_items`.
+    pub fn to_items(&self) -> Vec<ItemType> {
+        let mut items: Vec<ItemType> = self.equipped.into_iter().collect();
+
+        for stack in &self.stacks {
+            items.extend(std::iter::repeat(stack.itype).take(stack.count.max(0) as usize));
+        }
+
+        items
+    }
+
+    pub fn stacks(&self) -> &[ItemStack] {
+        &self.stacks
+    }
+
+    /// Total count of an item, including the equipped one.
+    pub fn count(&self, itype: ItemType) -> i32 {
+        let equipped = (self.equipped == Some(itype)) as i32;
+
+        equipped + self
+            .stacks
+            .iter()
+            .filter(|stack| stack.itype == itype)
+            .map(|stack| stack.count)
+            .sum::<i32>()
+    }
+
+    /// Adds as many of `count` items as fit and returns how many didn't.
+    /// Equipment goes straight into an empty equipment slot.
+    pub fn add(&mut self, itype: ItemType, mut count: i32) -> i32 {
+        if itype.is_equipment() && self.equipped.is_none() && count > 0 {
+            self.equipped = Some(itype);
+            count -= 1;
+        }
+
+        self.add_to_stacks(itype, count)
+    }
+
+    fn add_to_stacks(&mut self, itype: ItemType, mut count: i32) -> i32 {
+        for stack in self.stacks.iter_mut().filter(|stack| stack.itype == itype) {
+            let space = itype.max_stack() - stack.count;
+            let moved = space.min(count).max(0);
+            stack.count += moved;
+            count -= moved;
+        }
+
+        while count > 0 && self.stacks.len() < self.capacity {
+            let moved = itype.max_stack().min(count);
+            self.stacks.push(ItemStack { itype, count: moved });
+            count -= moved;
+        }
+
+        count
+    }
+
+    /// Removes `count` items from the stacks if there are enough of them.
+    /// The equipped item is left alone.
+    pub fn remove(&mut self, itype: ItemType, mut count: i32) -> bool {
+        let stacked = self.count(itype) - (self.equipped == Some(itype)) as i32;
+        if stacked < count {
+            return false;
+        }
+
+        // Take from the last stacks first so full stacks stay full.
+        for stack in self.stacks.iter_mut().rev().filter(|stack| stack.itype == itype) {
+            let moved = stack.count.min(count);
+            stack.count -= moved;
+            count -= moved;
+        }
+        self.stacks.retain(|stack| stack.count > 0);
+
+        true
+    }
+
+    /// Moves a piece of equipment from the stacks into the equipment slot,
+    /// putting back whatever was equipped before.
+    pub fn equip(&mut self, itype: ItemType) -> bool {
+        if !itype.is_equipment() || !self.remove(itype, 1) {
+            return false;
+        }
+
+        if let Some(previous) = self.equipped.replace(itype) {
+            self.add_to_stacks(previous, 1);
+        }
+
+        true
+    }
+
+    /// Puts the equipped item back into the stacks, if there is room.
+    pub fn unequip(&mut self) -> Option<ItemType> {
+        let itype = self.equipped.take()?;
+
+        if self.add_to_stacks(itype, 1) > 0 {
+            self.equipped = Some(itype);
+            return None;
+        }
+
+        Some(itype)
+    }
+}
+
+impl From<&ItemTypeVec> for Inventory {
+    fn from(items: &ItemTypeVec) -> Self {
+        Self::from_items(&items.0)
+    }
+}
+
+impl From<&Inventory> for ItemTypeVec {
+    fn from(inventory: &Inventory) -> Self {
+        ItemTypeVec(inventory.to_items())
+    }
+}
+
+// LDtk only knows the flat `inventory` array, turn it into stacks once the
+// player is spawned.
+fn init_player_inventory(
+    mut commands: Commands,
+    players: Query<(Entity, &Player), (Added<Player>, Without<Inventory>)>,
+) {
+    for (entity, player) in players.iter() {
+        commands.entity(entity).insert(Inventory::from(&player.starting_items));
+    }
+}
@@ -0,0 +1,27 @@
+use bevy::prelude::*;
+
+/// The level every run is played on.
+pub const START_LEVEL: &str = "Start";
+
+/// Level loading as gameplay sees it. Gameplay asks for a level with
+/// `LoadLevel` and hears `LevelLoaded` once it is in, `MapPlugin` does the
+/// actual loading from LDtk.
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<LoadLevel>()
+            .add_event::<LevelLoaded>();
+    }
+}
+
+/// Replaces the loaded level with `identifier`. The global player stays.
+#[derive(Event)]
+pub struct LoadLevel {
+    pub identifier: String,
+}
+
+#[derive(Event)]
+pub struct LevelLoaded {
+    pub identifier: String,
+}
@@ -0,0 +1,130 @@
+use bevy::{app::PluginGroupBuilder, prelude::*};
+use actor::ActorPlugin;
+use aim::AimPlugin;
+use animation::AnimationPlugin;
+use boss::BossPlugin;
+use boss_ui::BossUiPlugin;
+use combat::CombatPlugin;
+use corpse::CorpsePlugin;
+use credits::CreditsPlugin;
+use effects::EffectsPlugin;
+use ending::EndingPlugin;
+use facing::FacingPlugin;
+use interact::InteractPlugin;
+use inventory::InventoryPlugin;
+use level::LevelPlugin;
+use loot::LootPlugin;
+use map::MapPlugin;
+use mercy::MercyPlugin;
+use morale::MoralePlugin;
+use movement::MovementPlugin;
+use patrol::PatrolPlugin;
+use perception::PerceptionPlugin;
+use pickup::PickupPlugin;
+use profile::ProfilePlugin;
+use projectile::ProjectilePlugin;
+use replay::ReplayPlugin;
+use run::RunPlugin;
+use run_summary::RunSummaryPlugin;
+use sheets::SheetsPlugin;
+use shop::ShopPlugin;
+use shop_ui::ShopUiPlugin;
+use spawner::SpawnerPlugin;
+use spells::SpellsPlugin;
+use tactics::TacticsPlugin;
+use targeting::TargetingPlugin;
+use view::ViewPlugin;
+
+pub mod actor;
+pub mod aim;
+pub mod animation;
+pub mod boss;
+pub mod boss_ui;
+pub mod combat;
+pub mod corpse;
+pub mod credits;
+pub mod effects;
+pub mod ending;
+pub mod facing;
+pub mod interact;
+pub mod inventory;
+pub mod level;
+pub mod loot;
+pub mod map;
+pub mod mercy;
+pub mod morale;
+pub mod movement;
+pub mod patrol;
+pub mod perception;
+pub mod pickup;
+pub mod profile;
+pub mod projectile;
+pub mod replay;
+pub mod rng;
+pub mod ron_asset;
+pub mod run;
+pub mod run_summary;
+pub mod sheets;
+pub mod shop;
+pub mod shop_ui;
+pub mod spawner;
+pub mod spells;
+pub mod tactics;
+pub mod targeting;
+pub mod util;
+pub mod view;
+mod menu;
+
+/// The game rules. Only needs `MinimalPlugins`, transforms and physics, so
+/// it can be driven headless from tests.
+pub struct GameplayPlugins;
+
+impl PluginGroup for GameplayPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(ActorPlugin)
+            .add(CombatPlugin)
+            .add(CorpsePlugin)
+            .add(InventoryPlugin)
+            .add(LootPlugin)
+            .add(InteractPlugin)
+            .add(PickupPlugin)
+            .add(ShopPlugin)
+            .add(SpellsPlugin)
+            .add(ProjectilePlugin)
+            .add(MoralePlugin)
+            .add(BossPlugin)
+            .add(SpawnerPlugin)
+            .add(TacticsPlugin)
+            .add(PatrolPlugin)
+            .add(PerceptionPlugin)
+            .add(TargetingPlugin)
+            .add(MercyPlugin)
+            .add(EndingPlugin)
+            .add(LevelPlugin)
+            .add(RunPlugin)
+            .add(ProfilePlugin::default())
+            .add(MovementPlugin)
+            .add(AimPlugin)
+            .add(FacingPlugin)
+            .add(ReplayPlugin::default())
+    }
+}
+
+/// Levels, sprites and UI on top of `GameplayPlugins`.
+pub struct PresentationPlugins;
+
+impl PluginGroup for PresentationPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(MapPlugin)
+            .add(SheetsPlugin)
+            .add(AnimationPlugin)
+            .add(ViewPlugin)
+            .add(EffectsPlugin)
+            .add(ShopUiPlugin)
+            .add(CreditsPlugin)
+            .add(BossUiPlugin)
+            .add(RunSummaryPlugin)
+    }
+}
@@ -0,0 +1,185 @@
+use bevy::{ecs::system::SystemParam, prelude::*, utils::HashMap};
+use serde::Deserialize;
+use crate::corpse::{scatter_items, CorpseConfig};
+use crate::interact::{Interact, InteractSet, Interactable};
+use crate::rng::GameRng;
+use crate::ron_asset::{RonAsset, RonAssetPlugin, RonAssets, RonHandle};
+use crate::util::*;
+
+// Guards against tables that reference each other in a loop.
+const MAX_NESTING: usize = 8;
+const CHEST_RANGE: f32 = 20.;
+
+pub struct LootPlugin;
+
+impl Plugin for LootPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(RonAssetPlugin::<LootTableSet>::default())
+            .add_systems(Update, mark_chests.before(InteractSet))
+            .add_systems(Update, open_chests.after(InteractSet));
+    }
+}
+
+/// Every loot table of the game by name. Loaded from a `.loot.ron` file,
+/// LDtk `Chest` and `Enemy` entities refer to the tables by name.
+#[derive(Asset, TypePath, Deserialize, Default)]
+pub struct LootTableSet {
+    pub tables: HashMap<String, LootTable>,
+}
+
+impl RonAsset for LootTableSet {
+    const PATH: &'static str = "loot/tables.loot.ron";
+    const EXTENSIONS: &'static [&'static str] = &["loot.ron"];
+}
+
+#[derive(Deserialize, Clone)]
+pub struct LootTable {
+    /// How many times `entries` is rolled, inclusive.
+    #[serde(default = "one_roll")]
+    pub rolls: (i32, i32),
+    /// Dropped every time, on top of the rolls.
+    #[serde(default)]
+    pub guaranteed: Vec<LootDrop>,
+    #[serde(default)]
+    pub entries: Vec<LootEntry>,
+}
+
+fn one_roll() -> (i32, i32) {
+    (1, 1)
+}
+
+#[derive(Deserialize, Clone)]
+pub struct LootEntry {
+    pub weight: u32,
+    pub drop: LootDrop,
+}
+
+#[derive(Deserialize, Clone)]
+pub enum LootDrop {
+    Nothing,
+    /// An item with a count between the two values, inclusive.
+    Item(ItemType, (i32, i32)),
+    /// Rolls another table by name.
+    Table(String),
+}
+
+impl LootTableSet {
+    /// Rolls the table called `name`, returning the drops stacked by item
+    /// type. Unknown tables drop nothing.
+    pub fn roll(&self, name: &str, rng: &mut GameRng) -> Vec<(ItemType, i32)> {
+        let mut drops = Vec::new();
+        self.roll_into(name, rng, &mut drops, 0);
+        drops
+    }
+
+    fn roll_into(&self, name: &str, rng: &mut GameRng, drops: &mut Vec<(ItemType, i32)>, depth: usize) {
+        if depth > MAX_NESTING {
+            warn!("Loot table '{}' is nested too deep, is there a loop?", name);
+            return;
+        }
+        let Some(table) = self.tables.get(name) else {
+            warn!("No loot table named '{}'", name);
+            return;
+        };
+
+        for drop in &table.guaranteed {
+            self.resolve(drop, rng, drops, depth);
+        }
+
+        let total: u32 = table.entries.iter().map(|entry| entry.weight).sum();
+        if total == 0 {
+            return;
+        }
+
+        for _ in 0..rng.range_i32(table.rolls.0, table.rolls.1) {
+            let mut pick = rng.range_i32(0, total as i32 - 1) as u32;
+
+            for entry in &table.entries {
+                if pick < entry.weight {
+                    self.resolve(&entry.drop, rng, drops, depth);
+                    break;
+                }
+                pick -= entry.weight;
+            }
+        }
+    }
+
+    fn resolve(&self, drop: &LootDrop, rng: &mut GameRng, drops: &mut Vec<(ItemType, i32)>, depth: usize) {
+        match drop {
+            LootDrop::Nothing => {}
+            LootDrop::Item(itype, (min, max)) => {
+                let count = rng.range_i32(*min, *max);
+                if count > 0 {
+                    add_stack(drops, *itype, count);
+                }
+            }
+            LootDrop::Table(name) => self.roll_into(name, rng, drops, depth + 1),
+        }
+    }
+}
+
+/// Adds `count` items to the matching stack, or starts a new one.
+pub fn add_stack(stacks: &mut Vec<(ItemType, i32)>, itype: ItemType, count: i32) {
+    match stacks.iter_mut().find(|(stack, _)| *stack == itype) {
+        Some((_, stack_count)) => *stack_count += count,
+        None => stacks.push((itype, count)),
+    }
+}
+
+pub type LootTables = RonHandle<LootTableSet>;
+
+/// Rolls loot tables by name once they are loaded.
+#[derive(SystemParam)]
+pub struct LootRoller<'w> {
+    tables: RonAssets<'w, LootTableSet>,
+}
+
+impl LootRoller<'_> {
+    pub fn is_loaded(&self) -> bool {
+        self.tables.is_loaded()
+    }
+
+    pub fn roll(&self, name: &str, rng: &mut GameRng) -> Vec<(ItemType, i32)> {
+        let Some(set) = self.tables.get() else {
+            warn!("Loot tables aren't loaded, '{}' drops nothing", name);
+            return Vec::new();
+        };
+
+        set.roll(name, rng)
+    }
+}
+
+// Closed chests are opened with E.
+fn mark_chests(
+    mut commands: Commands,
+    chests: Query<(Entity, &Chest), Added<Chest>>,
+) {
+    for (entity, chest) in chests.iter() {
+        if chest.state == ChestState::Closed {
+            commands.entity(entity).insert(Interactable { range: CHEST_RANGE });
+        }
+    }
+}
+
+fn open_chests(
+    mut commands: Commands,
+    mut interactions: EventReader<Interact>,
+    mut chests: Query<(&mut Chest, &Transform)>,
+    loot: LootRoller,
+    config: Res<CorpseConfig>,
+    mut rng: ResMut<GameRng>,
+) {
+    for interaction in interactions.read() {
+        let Ok((mut chest, transform)) = chests.get_mut(interaction.target) else {
+            continue;
+        };
+
+        chest.state = ChestState::Open;
+        commands.entity(interaction.target).remove::<Interactable>();
+
+        if !chest.loot.is_empty() {
+            let drops = loot.roll(&chest.loot, &mut rng);
+            scatter_items(&mut commands, &mut rng, &config, &drops, transform.translation.truncate());
+        }
+    }
+}
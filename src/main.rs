@@ -1,12 +1,8 @@
 use bevy::prelude::*;
 use bevy_xpbd_2d::prelude::*;
-use actor::ActorPlugin;
-use map::MapPlugin;
-
-mod actor;
-mod map;
-mod util;
-mod menu;
+use acerola_jam_0_returner::{
+    profile::{ProfilePlugin, DEFAULT_PROFILE_PATH}, replay::ReplayPlugin, GameplayPlugins, PresentationPlugins,
+};
 
 fn main() {
     App::new()
@@ -14,8 +10,10 @@ fn main() {
             DefaultPlugins
                 .set(ImagePlugin::default_nearest()),
             PhysicsPlugins::default(),
-            ActorPlugin,
-            MapPlugin,
+            GameplayPlugins
+                .set(ReplayPlugin::from_args(std::env::args()))
+                .set(ProfilePlugin::saved_to(DEFAULT_PROFILE_PATH)),
+            PresentationPlugins,
         ))
         .run();
 }
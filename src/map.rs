@@ -15,6 +15,10 @@ use bevy_entitiles::{
 };
 use crate::util::*;
 use crate::actor::{Enemy, Player};
+use crate::boss::Boss;
+use crate::shop::Merchant;
+use crate::level::{LevelLoaded, LoadLevel, START_LEVEL};
+use crate::spawner::Spawner;
 
 
 pub struct MapPlugin;
@@ -29,6 +33,7 @@ impl Plugin for MapPlugin {
                 (
                     events,
                     hot_reload,
+                    load_requested,
                     load.run_if(resource_added::<LdtkLevelManager>),
                 ),
             )
@@ -37,8 +42,9 @@ impl Plugin for MapPlugin {
             .register_type::<Item>()
             .register_type::<Chest>()
             .register_type::<Door>()
+            .register_type::<Boss>()
+            .register_type::<Spawner>()
             .insert_resource(Msaa::Off)
-            .insert_resource(Gravity(Vec2::new(0., 0.)))
             .insert_resource(LdtkLoadConfig {
                 file_path: "assets/ldtk/test.ldtk".to_string(),
                 asset_path_prefix: "ldtk/".to_string(),
@@ -67,6 +73,9 @@ impl Plugin for MapPlugin {
             .register_ldtk_entity::<Door>("Door")
             .register_ldtk_entity::<Player>("Player")
             .register_ldtk_entity::<Enemy>("Enemy")
+            .register_ldtk_entity::<Merchant>("Merchant")
+            .register_ldtk_entity::<Boss>("Boss")
+            .register_ldtk_entity::<Spawner>("Spawner")
             .register_ldtk_entity_tag::<Actor>("actor")
             .register_ldtk_entity_tag::<Loot>("loot")
             .register_ldtk_entity_tag::<Object>("object");
@@ -79,9 +88,23 @@ pub fn load(
     mut commands: Commands,
     mut manager: ResMut<LdtkLevelManager>,
 ) {
-    manager.load(&mut commands, "Start".to_string(), None);
+    manager.load(&mut commands, START_LEVEL.to_string(), None);
 }
 
+pub fn load_requested(
+    mut commands: Commands,
+    mut manager: ResMut<LdtkLevelManager>,
+    mut requests: EventReader<LoadLevel>,
+) {
+    let Some(request) = requests.read().last() else {
+        return;
+    };
+
+    manager.unload_all(&mut commands);
+    manager.load(&mut commands, request.identifier.clone(), None);
+}
+
+
 pub fn hot_reload(
     input: Res<ButtonInput<KeyCode>>,
     mut manager: ResMut<LdtkLevelManager>,
@@ -106,11 +129,12 @@ pub fn hot_reload(
     }
 }
 
-pub fn events(mut ldtk_events: EventReader<LdtkEvent>) {
+pub fn events(mut ldtk_events: EventReader<LdtkEvent>, mut loaded: EventWriter<LevelLoaded>) {
     for event in ldtk_events.read() {
         match event {
             LdtkEvent::LevelLoaded(level) => {
                 println!("Level loaded: {}", level.identifier);
+                loaded.send(LevelLoaded { identifier: level.identifier.clone() });
             }
             LdtkEvent::LevelUnloaded(level) => {
                 println!("Level unloaded: {}", level.identifier);
@@ -0,0 +1,125 @@
+use bevy::prelude::*;
+use bevy_yarnspinner::prelude::*;
+use crate::actor::{Cower, Dead, Enemy, Flee, Player, Surrender};
+use crate::interact::{Interact, InteractSet, Interactable};
+
+pub struct MercyPlugin;
+
+impl Plugin for MercyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MercyConfig>()
+            .register_type::<MercyConfig>()
+            .add_event::<EnemySpared>()
+            .add_systems(Update, mark_beaten_enemies.before(InteractSet))
+            .add_systems(Update, (
+                spare_enemies,
+                mercy_ending,
+            ).chain().after(InteractSet));
+    }
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct MercyConfig {
+    /// How close the player has to be to spare an enemy.
+    pub range: f32,
+    /// Enemies at or below this HP can be spared whatever state they are in.
+    pub low_hp: f32,
+}
+
+impl Default for MercyConfig {
+    fn default() -> Self {
+        Self {
+            range: 20.,
+            low_hp: 1.,
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct EnemySpared {
+    pub enemy: Entity,
+    pub position: Vec2,
+}
+
+/// Starts a Yarn node, cutting off whatever is being said. Does nothing
+/// without a dialogue runner, like in headless tests.
+pub(crate) fn start_node(dialogue_runner: &mut Query<&mut DialogueRunner>, node: &str) {
+    let Ok(mut dr) = dialogue_runner.get_single_mut() else {
+        return;
+    };
+
+    if dr.current_node().is_some() {
+        dr.stop();
+    }
+    dr.start_node(node);
+}
+
+// Beaten enemies, the ones fleeing, cowering, surrendered or at low HP, can
+// be spared with E.
+fn mark_beaten_enemies(
+    mut commands: Commands,
+    enemies: Query<(Entity, &Enemy, Has<Flee>, Has<Cower>, Has<Surrender>, Has<Interactable>), Without<Dead>>,
+    config: Res<MercyConfig>,
+) {
+    for (entity, enemy, fleeing, cowering, surrendered, marked) in enemies.iter() {
+        // Enemies at 0 HP are dying, not beaten, even before they are `Dead`.
+        let beaten = enemy.hp > 0. && (fleeing || cowering || surrendered || enemy.hp <= config.low_hp);
+
+        if beaten && !marked {
+            commands.entity(entity).insert(Interactable { range: config.range });
+        } else if !beaten && marked {
+            commands.entity(entity).remove::<Interactable>();
+        }
+    }
+}
+
+// E on a beaten enemy lets it go instead of finishing it off.
+fn spare_enemies(
+    mut commands: Commands,
+    mut interactions: EventReader<Interact>,
+    enemies: Query<(&Enemy, &GlobalTransform), Without<Dead>>,
+    mut player_q: Query<&mut Player>,
+    mut dialogue_runner: Query<&mut DialogueRunner>,
+    mut spared: EventWriter<EnemySpared>,
+) {
+    let Ok(mut player) = player_q.get_single_mut() else {
+        return;
+    };
+
+    for interaction in interactions.read() {
+        let Ok((enemy, transform)) = enemies.get(interaction.target) else {
+            continue;
+        };
+
+        player.spared += 1;
+        spared.send(EnemySpared { enemy: interaction.target, position: transform.translation().truncate() });
+
+        if let Ok(mut dr) = dialogue_runner.get_single_mut() {
+            let _ = dr.variable_storage_mut().set("$name".to_string(), YarnValue::String(enemy.name.clone()));
+        }
+        start_node(&mut dialogue_runner, "Spared");
+
+        commands.entity(interaction.target).despawn_recursive();
+    }
+}
+
+// Once the last enemy is gone the player still has to grow stronger, unless
+// they never killed anyone, which the ending subsystem picks up.
+fn mercy_ending(
+    mut spared: EventReader<EnemySpared>,
+    enemies: Query<(), (With<Enemy>, Without<Dead>)>,
+    player_q: Query<&Player>,
+    mut dialogue_runner: Query<&mut DialogueRunner>,
+) {
+    if spared.read().count() == 0 || !enemies.is_empty() {
+        return;
+    }
+    let Ok(player) = player_q.get_single() else {
+        return;
+    };
+
+    if player.killed > 0 {
+        start_node(&mut dialogue_runner, "Reset");
+    }
+}
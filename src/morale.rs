@@ -0,0 +1,136 @@
+use bevy::prelude::*;
+use crate::actor::{Cower, Dead, Enemy, Flee, Surrender};
+use crate::animation::{AnimState, AnimationController};
+use crate::movement::MovementController;
+
+pub struct MoralePlugin;
+
+impl Plugin for MoralePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MoraleConfig>()
+            .register_type::<MoraleConfig>()
+            .register_type::<Morale>()
+            .add_systems(Update, (
+                decay_fear,
+                fear_contagion,
+                group_morale,
+                cower,
+            ).chain());
+    }
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct MoraleConfig {
+    /// Fear lost per second.
+    pub decay: f32,
+    /// How far fear spreads when an enemy dies or starts fleeing.
+    pub contagion_radius: f32,
+    pub death_fear: f32,
+    pub flee_fear: f32,
+    /// Allies closer than this make an enemy braver.
+    pub group_radius: f32,
+    /// Fraction of fear each nearby ally takes away, up to `max_group_courage`.
+    pub group_courage: f32,
+    pub max_group_courage: f32,
+    /// Effective fear thresholds for the enemy states.
+    pub follow_below: f32,
+    pub flee_above: f32,
+    pub cower_above: f32,
+    pub surrender_above: f32,
+}
+
+impl Default for MoraleConfig {
+    fn default() -> Self {
+        Self {
+            decay: 2.,
+            contagion_radius: 80.,
+            death_fear: 20.,
+            flee_fear: 8.,
+            group_radius: 60.,
+            group_courage: 0.15,
+            max_group_courage: 0.6,
+            follow_below: 10.,
+            flee_above: 50.,
+            cower_above: 80.,
+            surrender_above: 120.,
+        }
+    }
+}
+
+/// How afraid an enemy really is, its `Enemy::fear` after the courage it
+/// takes from nearby allies. The state machine decides on this.
+#[derive(Component, Reflect, Default)]
+pub struct Morale {
+    pub fear: f32,
+}
+
+fn decay_fear(
+    mut enemies: Query<&mut Enemy, (Without<Dead>, Without<Surrender>)>,
+    config: Res<MoraleConfig>,
+    time: Res<Time>,
+) {
+    for mut enemy in enemies.iter_mut() {
+        if enemy.fear > 0. {
+            enemy.fear = (enemy.fear - config.decay * time.delta_seconds()).max(0.);
+        }
+    }
+}
+
+// Seeing an ally die or run scares everyone close by, the closer the worse.
+fn fear_contagion(
+    died: Query<&GlobalTransform, (With<Enemy>, Added<Dead>)>,
+    fled: Query<&GlobalTransform, (With<Enemy>, Added<Flee>)>,
+    mut enemies: Query<(&mut Enemy, &GlobalTransform), Without<Dead>>,
+    config: Res<MoraleConfig>,
+) {
+    let sources = died
+        .iter()
+        .map(|transform| (transform.translation().truncate(), config.death_fear))
+        .chain(fled.iter().map(|transform| (transform.translation().truncate(), config.flee_fear)));
+
+    for (source, amount) in sources {
+        for (mut enemy, transform) in enemies.iter_mut() {
+            let distance = transform.translation().truncate().distance(source);
+
+            // The one that fled doesn't scare itself.
+            if distance > 0. && distance < config.contagion_radius {
+                enemy.fear += amount * (1. - distance / config.contagion_radius);
+            }
+        }
+    }
+}
+
+fn group_morale(
+    mut enemies: Query<(Entity, &Enemy, &GlobalTransform, &mut Morale)>,
+    alive: Query<(Entity, &GlobalTransform), (With<Enemy>, Without<Dead>)>,
+    config: Res<MoraleConfig>,
+) {
+    for (entity, enemy, transform, mut morale) in enemies.iter_mut() {
+        let position = transform.translation().truncate();
+        let allies = alive
+            .iter()
+            .filter(|(ally, ally_t)| {
+                *ally != entity && ally_t.translation().truncate().distance(position) < config.group_radius
+            })
+            .count();
+
+        let courage = (allies as f32 * config.group_courage).min(config.max_group_courage);
+        morale.fear = enemy.fear * (1. - courage);
+    }
+}
+
+fn cower(
+    mut anims: Query<&mut AnimationController>,
+    mut cowering: Query<(&Children, &mut MovementController), Or<(With<Cower>, With<Surrender>)>>,
+) {
+    for (children, mut controller) in cowering.iter_mut() {
+        controller.input = Vec2::ZERO;
+
+        for child in children {
+            if let Ok(mut anim) = anims.get_mut(*child) {
+                anim.set_base(AnimState::Idle);
+            }
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+use bevy_xpbd_2d::prelude::*;
+use crate::util::*;
+
+pub struct MovementPlugin;
+
+impl Plugin for MovementPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MovementConfig>()
+            .register_type::<MovementConfig>()
+            .register_type::<MovementController>()
+            .register_type::<Knockback>()
+            .configure_sets(Update, (
+                MovementSet::Steer,
+                MovementSet::Adjust,
+                MovementSet::Apply,
+            ).chain())
+            .add_systems(Update, player_movement_input.in_set(MovementSet::Steer))
+            .add_systems(Update, apply_movement.in_set(MovementSet::Apply));
+    }
+}
+
+/// Input and AI states write `MovementController::input` in `Steer`, group
+/// behaviours bend it in `Adjust`, then `apply_movement` turns it into
+/// velocity.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MovementSet {
+    Steer,
+    Adjust,
+    Apply,
+}
+
+/// Tuning values for the movement controller. Registered for reflection so it
+/// can be edited at runtime.
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct MovementConfig {
+    pub max_speed: f32,
+    /// The player's max speed while holding shift.
+    pub sprint_speed: f32,
+    /// Speed gained per second while input is held.
+    pub acceleration: f32,
+    /// Speed lost per second once input is released.
+    pub deceleration: f32,
+    /// Shapes the acceleration curve. 1 is linear, higher values ease out as
+    /// the mover approaches `max_speed`.
+    pub acceleration_curve: f32,
+    /// Extra acceleration multiplier when the input points away from the
+    /// current velocity, so turning around feels snappy.
+    pub turn_multiplier: f32,
+    /// Fraction of knockback velocity that is lost every second.
+    pub knockback_decay: f32,
+}
+
+impl Default for MovementConfig {
+    fn default() -> Self {
+        Self {
+            max_speed: 100.,
+            sprint_speed: 150.,
+            acceleration: 800.,
+            deceleration: 1000.,
+            acceleration_curve: 1.,
+            turn_multiplier: 2.,
+            knockback_decay: 8.,
+        }
+    }
+}
+
+/// Desired movement direction for an entity, written by input or AI and
+/// turned into velocity by `apply_movement`.
+#[derive(Component, Reflect, Default)]
+pub struct MovementController {
+    pub input: Vec2,
+    /// Overrides `MovementConfig::max_speed`, e.g. for slower enemies.
+    pub max_speed: Option<f32>,
+}
+
+/// Velocity added on top of controlled movement, decaying over time.
+#[derive(Component, Reflect, Default)]
+pub struct Knockback {
+    pub velocity: Vec2,
+    // What `apply_movement` added to the velocity last frame.
+    applied: Vec2,
+}
+
+impl Knockback {
+    pub fn apply(&mut self, impulse: Vec2) {
+        self.velocity += impulse;
+    }
+}
+
+fn player_movement_input(
+    mut player_q: Query<&mut MovementController, With<PlayerMover>>,
+    input: Res<ButtonInput<KeyCode>>,
+    config: Res<MovementConfig>,
+) {
+    let Ok(mut controller) = player_q.get_single_mut() else {
+        return;
+    };
+
+    let mut direction = Vec2::ZERO;
+
+    if input.pressed(KeyCode::KeyA) {
+        direction.x -= 1.;
+    }
+    if input.pressed(KeyCode::KeyD) {
+        direction.x += 1.;
+    }
+    if input.pressed(KeyCode::KeyW) {
+        direction.y += 1.;
+    }
+    if input.pressed(KeyCode::KeyS) {
+        direction.y -= 1.;
+    }
+
+    controller.input = direction.normalize_or_zero();
+    controller.max_speed = input.pressed(KeyCode::ShiftLeft).then_some(config.sprint_speed);
+}
+
+fn apply_movement(
+    mut movers: Query<(&MovementController, &mut LinearVelocity, Option<&mut Knockback>)>,
+    config: Res<MovementConfig>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+
+    for (controller, mut velocity, knockback) in movers.iter_mut() {
+        let mut applied = Vec2::ZERO;
+        let mut added = Vec2::ZERO;
+
+        // A fresh impulse is only in `velocity`, so take out what was really
+        // added last frame, not the whole knockback.
+        if let Some(mut knockback) = knockback {
+            applied = knockback.applied;
+            added = knockback.velocity;
+            knockback.applied = added;
+            knockback.velocity *= (1. - config.knockback_decay * dt).max(0.);
+        }
+
+        // Work on the controlled part of the velocity only, so knockback
+        // does not get eaten by the max speed clamp.
+        let current = velocity.0 - applied;
+        let max_speed = controller.max_speed.unwrap_or(config.max_speed);
+        let target = controller.input.clamp_length_max(1.) * max_speed;
+
+        let controlled = if target == Vec2::ZERO {
+            move_towards(current, Vec2::ZERO, config.deceleration * dt)
+        } else {
+            let progress = (current.length() / max_speed).clamp(0., 1.);
+            let mut rate = config.acceleration
+                * (1. - progress).powf(config.acceleration_curve - 1.).max(0.1);
+
+            if current.dot(target) < 0. {
+                rate *= config.turn_multiplier;
+            }
+
+            move_towards(current, target, rate * dt)
+        };
+
+        velocity.0 = controlled + added;
+    }
+}
+
+fn move_towards(current: Vec2, target: Vec2, max_delta: f32) -> Vec2 {
+    let delta = target - current;
+    let distance = delta.length();
+
+    if distance <= max_delta || distance == 0. {
+        target
+    } else {
+        current + delta / distance * max_delta
+    }
+}
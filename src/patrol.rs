@@ -0,0 +1,201 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use crate::actor::{Enemy, Patrol, ReturnToPost, Wander};
+use crate::animation::{AnimState, AnimationController};
+use crate::movement::{MovementController, MovementSet};
+use crate::rng::GameRng;
+
+// LDtk points are in cells of the entity layer grid.
+const GRID_SIZE: f32 = 16.;
+
+pub struct PatrolPlugin;
+
+impl Plugin for PatrolPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PatrolConfig>()
+            .register_type::<PatrolConfig>()
+            .register_type::<Post>()
+            .add_systems(Update, init_posts)
+            .add_systems(Update, (
+                patrol,
+                wander,
+                return_to_post,
+            ).in_set(MovementSet::Steer));
+    }
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct PatrolConfig {
+    pub patrol_speed: f32,
+    pub wander_speed: f32,
+    pub return_speed: f32,
+    /// How far from its post a wandering enemy strolls.
+    pub wander_radius: f32,
+    /// Seconds a wandering enemy rests between strolls, picked at random.
+    pub wander_pause: (f32, f32),
+    /// Seconds a patrolling enemy stops at each waypoint.
+    pub waypoint_pause: f32,
+    /// Close enough to a waypoint or goal to count as there.
+    pub arrive_distance: f32,
+}
+
+impl Default for PatrolConfig {
+    fn default() -> Self {
+        Self {
+            patrol_speed: 10.,
+            wander_speed: 8.,
+            return_speed: 12.,
+            wander_radius: 32.,
+            wander_pause: (1., 3.),
+            waypoint_pause: 1.,
+            arrive_distance: 3.,
+        }
+    }
+}
+
+/// Where an enemy belongs when it isn't chasing or running from anyone.
+#[derive(Component, Reflect)]
+pub struct Post {
+    /// Where the enemy was placed.
+    pub home: Vec2,
+    pub route: Vec<Vec2>,
+    /// Index of the waypoint the enemy is heading for.
+    pub next: usize,
+}
+
+impl Post {
+    /// The spot to go back to after losing the player: the waypoint it was
+    /// heading for, or home for enemies without a route.
+    pub fn anchor(&self) -> Vec2 {
+        self.route.get(self.next).copied().unwrap_or(self.home)
+    }
+}
+
+fn init_posts(mut commands: Commands, enemies: Query<(Entity, &Enemy, &Transform), Added<Enemy>>) {
+    for (entity, enemy, transform) in enemies.iter() {
+        let route = enemy
+            .patrol
+            .iter()
+            .map(|cell| Vec2::new(cell.x as f32 + 0.5, -(cell.y as f32 + 0.5)) * GRID_SIZE)
+            .collect();
+
+        commands.entity(entity).insert(Post {
+            home: transform.translation.truncate(),
+            route,
+            next: 0,
+        });
+    }
+}
+
+// Walks towards `goal` and returns whether it got there.
+fn walk_to(
+    controller: &mut MovementController,
+    children: &Children,
+    anims: &mut Query<&mut AnimationController>,
+    position: Vec2,
+    goal: Vec2,
+    speed: f32,
+    arrive_distance: f32,
+) -> bool {
+    let arrived = position.distance(goal) <= arrive_distance;
+
+    controller.input = if arrived { Vec2::ZERO } else { (goal - position).normalize_or_zero() };
+    controller.max_speed = Some(speed);
+
+    for child in children {
+        if let Ok(mut anim) = anims.get_mut(*child) {
+            anim.set_base(if arrived { AnimState::Idle } else { AnimState::Walk });
+        }
+    }
+
+    arrived
+}
+
+fn patrol(
+    mut patrols: Query<(&mut Patrol, &mut Post, &GlobalTransform, &mut MovementController, &Children)>,
+    mut anims: Query<&mut AnimationController>,
+    config: Res<PatrolConfig>,
+    time: Res<Time>,
+) {
+    for (mut patrol, mut post, transform, mut controller, children) in patrols.iter_mut() {
+        if !patrol.pause.tick(time.delta()).finished() {
+            continue;
+        }
+
+        let position = transform.translation().truncate();
+        let arrived = walk_to(
+            &mut controller,
+            children,
+            &mut anims,
+            position,
+            post.anchor(),
+            config.patrol_speed,
+            config.arrive_distance,
+        );
+
+        if arrived && !post.route.is_empty() {
+            post.next = (post.next + 1) % post.route.len();
+            patrol.pause = Timer::from_seconds(config.waypoint_pause, TimerMode::Once);
+        }
+    }
+}
+
+fn wander(
+    mut wanderers: Query<(&mut Wander, &Post, &GlobalTransform, &mut MovementController, &Children)>,
+    mut anims: Query<&mut AnimationController>,
+    mut rng: ResMut<GameRng>,
+    config: Res<PatrolConfig>,
+    time: Res<Time>,
+) {
+    for (mut wander, post, transform, mut controller, children) in wanderers.iter_mut() {
+        let Some(goal) = wander.goal else {
+            controller.input = Vec2::ZERO;
+
+            if wander.pause.tick(time.delta()).finished() {
+                // Square root keeps the picks evenly spread over the circle
+                // instead of bunching up in the middle.
+                let distance = rng.next_f32().sqrt() * config.wander_radius;
+                let angle = rng.next_f32() * TAU;
+                wander.goal = Some(post.home + Vec2::from_angle(angle) * distance);
+            }
+            continue;
+        };
+
+        let position = transform.translation().truncate();
+        let arrived = walk_to(
+            &mut controller,
+            children,
+            &mut anims,
+            position,
+            goal,
+            config.wander_speed,
+            config.arrive_distance,
+        );
+
+        if arrived {
+            let (min, max) = config.wander_pause;
+            wander.goal = None;
+            wander.pause = Timer::from_seconds(rng.range_f32(min, max), TimerMode::Once);
+        }
+    }
+}
+
+fn return_to_post(
+    mut returning: Query<(&Post, &GlobalTransform, &mut MovementController, &Children), With<ReturnToPost>>,
+    mut anims: Query<&mut AnimationController>,
+    config: Res<PatrolConfig>,
+) {
+    for (post, transform, mut controller, children) in returning.iter_mut() {
+        walk_to(
+            &mut controller,
+            children,
+            &mut anims,
+            transform.translation().truncate(),
+            post.anchor(),
+            config.return_speed,
+            config.arrive_distance,
+        );
+    }
+}
@@ -0,0 +1,195 @@
+use std::f32::consts::FRAC_PI_3;
+
+use bevy::prelude::*;
+use bevy_xpbd_2d::prelude::*;
+use crate::actor::{Dead, Enemy};
+use crate::combat::MeleeSwing;
+use crate::facing::Facing;
+use crate::projectile::{Projectile, ProjectileTarget};
+use crate::util::*;
+
+// Walls between an enemy and the player are rarely more than a few
+// colliders deep.
+const MAX_RAY_HITS: u32 = 8;
+
+pub struct PerceptionPlugin;
+
+impl Plugin for PerceptionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PerceptionConfig>()
+            .register_type::<PerceptionConfig>()
+            .register_type::<Perception>()
+            .add_event::<Noise>()
+            .add_systems(Update, (
+                make_noise,
+                look,
+                listen,
+                forget,
+            ).chain());
+    }
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct PerceptionConfig {
+    pub sight_range: f32,
+    /// Half the width of the vision cone, in radians.
+    pub sight_angle: f32,
+    /// Closer than this the player is noticed from any side.
+    pub feel_range: f32,
+    /// Seconds an enemy keeps chasing the last place it saw or heard the
+    /// player.
+    pub memory: f32,
+    /// How far the different noises the player makes carry.
+    pub attack_noise: f32,
+    pub cast_noise: f32,
+    pub footstep_noise: f32,
+    /// The player's footsteps are heard when moving faster than this, which
+    /// is above walking speed, so only sprinting is loud.
+    pub loud_speed: f32,
+}
+
+impl Default for PerceptionConfig {
+    fn default() -> Self {
+        Self {
+            sight_range: 60.,
+            sight_angle: FRAC_PI_3,
+            feel_range: 24.,
+            memory: 4.,
+            attack_noise: 120.,
+            cast_noise: 100.,
+            footstep_noise: 40.,
+            loud_speed: 120.,
+        }
+    }
+}
+
+/// What an enemy knows about where the player is.
+#[derive(Component, Reflect, Default)]
+pub struct Perception {
+    pub sees_player: bool,
+    /// Where the player was last seen or heard, until forgotten.
+    pub last_known: Option<Vec2>,
+    memory: Timer,
+}
+
+impl Perception {
+    pub fn aware(&self) -> bool {
+        self.sees_player || self.last_known.is_some()
+    }
+
+    /// Where to go after the player: straight at them while in sight,
+    /// otherwise where they were last noticed.
+    pub fn chase_point(&self, player: Vec2) -> Vec2 {
+        if self.sees_player {
+            player
+        } else {
+            self.last_known.unwrap_or(player)
+        }
+    }
+
+    fn notice(&mut self, position: Vec2, memory: f32) {
+        self.last_known = Some(position);
+        self.memory = Timer::from_seconds(memory, TimerMode::Once);
+    }
+}
+
+/// Something the player did that enemies within `radius` hear.
+#[derive(Event)]
+pub struct Noise {
+    pub position: Vec2,
+    pub radius: f32,
+}
+
+fn make_noise(
+    swings: Query<(), Added<MeleeSwing>>,
+    casts: Query<&Projectile, Added<Projectile>>,
+    player_q: Query<(&GlobalTransform, &LinearVelocity), With<PlayerMover>>,
+    mut noises: EventWriter<Noise>,
+    config: Res<PerceptionConfig>,
+) {
+    let Ok((transform, velocity)) = player_q.get_single() else {
+        return;
+    };
+    let position = transform.translation().truncate();
+
+    if !swings.is_empty() {
+        noises.send(Noise { position, radius: config.attack_noise });
+    }
+    if casts.iter().any(|projectile| projectile.target == ProjectileTarget::Enemies) {
+        noises.send(Noise { position, radius: config.cast_noise });
+    }
+    if velocity.0.length() > config.loud_speed {
+        noises.send(Noise { position, radius: config.footstep_noise });
+    }
+}
+
+// Enemies see the player inside their vision cone, unless a wall is in the
+// way.
+fn look(
+    mut enemies: Query<(Entity, &mut Perception, &Facing, &GlobalTransform), (With<Enemy>, Without<Dead>)>,
+    player_q: Query<(Entity, &GlobalTransform), With<PlayerMover>>,
+    bodies: Query<&RigidBody>,
+    spatial_query: SpatialQuery,
+    config: Res<PerceptionConfig>,
+) {
+    // Nobody to notice, and nothing worth remembering about a player that
+    // is gone.
+    let Ok((player, player_t)) = player_q.get_single() else {
+        for (_, mut perception, ..) in enemies.iter_mut() {
+            perception.sees_player = false;
+            perception.last_known = None;
+        }
+        return;
+    };
+    let player_position = player_t.translation().truncate();
+
+    for (entity, mut perception, facing, transform) in enemies.iter_mut() {
+        let position = transform.translation().truncate();
+        let to_player = player_position - position;
+        let distance = to_player.length();
+
+        let in_cone = distance <= config.sight_range
+            && facing.direction.to_vec().angle_between(to_player).abs() <= config.sight_angle;
+
+        let sees = (distance <= config.feel_range || in_cone) && Direction2d::new(to_player).is_ok_and(|direction| {
+            let filter = SpatialQueryFilter::default().with_excluded_entities([entity, player]);
+            !spatial_query
+                .ray_hits(position, direction, distance, MAX_RAY_HITS, true, filter)
+                .iter()
+                .any(|hit| bodies.get(hit.entity).is_ok_and(|body| body.is_static()))
+        });
+
+        perception.sees_player = sees;
+        if sees {
+            perception.notice(player_position, config.memory);
+        }
+    }
+}
+
+// Noise carries through walls.
+fn listen(
+    mut noises: EventReader<Noise>,
+    mut enemies: Query<(&mut Perception, &GlobalTransform), (With<Enemy>, Without<Dead>)>,
+    config: Res<PerceptionConfig>,
+) {
+    for noise in noises.read() {
+        for (mut perception, transform) in enemies.iter_mut() {
+            if transform.translation().truncate().distance(noise.position) <= noise.radius {
+                perception.notice(noise.position, config.memory);
+            }
+        }
+    }
+}
+
+fn forget(mut enemies: Query<&mut Perception>, time: Res<Time>) {
+    for mut perception in enemies.iter_mut() {
+        if perception.sees_player || perception.last_known.is_none() {
+            continue;
+        }
+
+        if perception.memory.tick(time.delta()).finished() {
+            perception.last_known = None;
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use bevy::prelude::*;
+use crate::actor::Player;
+use crate::interact::{Interact, InteractSet, Interactable};
+use crate::inventory::Inventory;
+use crate::util::*;
+
+pub struct PickupPlugin;
+
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PickupConfig>()
+            .register_type::<PickupConfig>()
+            .add_event::<ItemPickedUp>()
+            .add_systems(Update, mark_items.before(InteractSet))
+            .add_systems(Update, (
+                magnet_pull,
+                auto_collect,
+                pick_up_items,
+            ).chain().after(InteractSet));
+    }
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct PickupConfig {
+    /// How close an item has to be to be picked up.
+    pub range: f32,
+    /// Item types that are picked up just by walking over them.
+    pub auto_collect: Vec<ItemType>,
+    /// Auto-collected items closer than this drift toward the player.
+    pub magnet_range: f32,
+    pub magnet_speed: f32,
+}
+
+impl Default for PickupConfig {
+    fn default() -> Self {
+        Self {
+            range: 20.,
+            auto_collect: vec![ItemType::Coins],
+            magnet_range: 48.,
+            magnet_speed: 150.,
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct ItemPickedUp {
+    pub itype: ItemType,
+    pub count: i32,
+    pub position: Vec2,
+}
+
+fn player_position(player_q: &Query<&GlobalTransform, With<PlayerMover>>) -> Option<Vec2> {
+    player_q.get_single().ok().map(|transform| transform.translation().truncate())
+}
+
+// Items that aren't auto-collected are picked up with E.
+fn mark_items(
+    mut commands: Commands,
+    items: Query<(Entity, &Item), Added<Item>>,
+    config: Res<PickupConfig>,
+) {
+    for (entity, item) in items.iter() {
+        if !config.auto_collect.contains(&item.itype) {
+            commands.entity(entity).insert(Interactable { range: config.range });
+        }
+    }
+}
+
+fn magnet_pull(
+    mut items: Query<(&mut Transform, &Item)>,
+    player_q: Query<&GlobalTransform, With<PlayerMover>>,
+    config: Res<PickupConfig>,
+    time: Res<Time>,
+) {
+    let Some(player) = player_position(&player_q) else {
+        return;
+    };
+
+    for (mut transform, item) in items.iter_mut() {
+        if !config.auto_collect.contains(&item.itype) {
+            continue;
+        }
+
+        let offset = player - transform.translation.truncate();
+        let distance = offset.length();
+        if distance < config.magnet_range {
+            let step = (config.magnet_speed * time.delta_seconds()).min(distance);
+            transform.translation += (offset.normalize_or_zero() * step).extend(0.);
+        }
+    }
+}
+
+fn auto_collect(
+    mut commands: Commands,
+    items: Query<(Entity, &GlobalTransform, &Item)>,
+    player_q: Query<&GlobalTransform, With<PlayerMover>>,
+    mut inventory_q: Query<&mut Inventory, With<Player>>,
+    mut picked_up: EventWriter<ItemPickedUp>,
+    config: Res<PickupConfig>,
+) {
+    let Some(player) = player_position(&player_q) else {
+        return;
+    };
+    let Ok(mut inventory) = inventory_q.get_single_mut() else {
+        return;
+    };
+
+    for (entity, transform, item) in items.iter() {
+        let position = transform.translation().truncate();
+
+        if config.auto_collect.contains(&item.itype) && position.distance(player) < config.range {
+            collect(&mut commands, &mut inventory, &mut picked_up, entity, item, position);
+        }
+    }
+}
+
+fn pick_up_items(
+    mut commands: Commands,
+    mut interactions: EventReader<Interact>,
+    items: Query<(&GlobalTransform, &Item)>,
+    mut inventory_q: Query<&mut Inventory, With<Player>>,
+    mut picked_up: EventWriter<ItemPickedUp>,
+) {
+    let Ok(mut inventory) = inventory_q.get_single_mut() else {
+        return;
+    };
+
+    for interaction in interactions.read() {
+        let Ok((transform, item)) = items.get(interaction.target) else {
+            continue;
+        };
+
+        collect(&mut commands, &mut inventory, &mut picked_up, interaction.target, item, transform.translation().truncate());
+    }
+}
+
+// Takes as much of the item as fits, whatever doesn't stays on the ground.
+fn collect(
+    commands: &mut Commands,
+    inventory: &mut Inventory,
+    picked_up: &mut EventWriter<ItemPickedUp>,
+    entity: Entity,
+    item: &Item,
+    position: Vec2,
+) {
+    let left = inventory.add(item.itype, item.count);
+    let taken = item.count - left;
+    if taken == 0 {
+        return;
+    }
+
+    picked_up.send(ItemPickedUp {
+        itype: item.itype,
+        count: taken,
+        position,
+    });
+
+    if left > 0 {
+        commands.entity(entity).insert(Item { itype: item.itype, count: left });
+    } else {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,105 @@
+use std::{collections::BTreeMap, fmt::Write as _, path::PathBuf};
+
+use bevy::prelude::*;
+
+const FORMAT_HEADER: &str = "returner-profile 1";
+pub const DEFAULT_PROFILE_PATH: &str = "returner.profile";
+
+/// Progress that outlives a single game, like which endings were reached.
+/// Without a path the profile only lives in memory, which is what tests use.
+#[derive(Default)]
+pub struct ProfilePlugin {
+    pub path: Option<PathBuf>,
+}
+
+impl ProfilePlugin {
+    pub fn saved_to(path: impl Into<PathBuf>) -> Self {
+        Self { path: Some(path.into()) }
+    }
+}
+
+impl Plugin for ProfilePlugin {
+    fn build(&self, app: &mut App) {
+        let profile = match &self.path {
+            Some(path) if path.exists() => Profile::load(path).unwrap_or_else(|err| {
+                error!("Could not load profile {}: {}", path.display(), err);
+                Profile::default()
+            }),
+            _ => Profile::default(),
+        };
+
+        app.insert_resource(Profile {
+            path: self.path.clone(),
+            ..profile
+        });
+    }
+}
+
+#[derive(Resource, Default, Clone, PartialEq, Debug)]
+pub struct Profile {
+    /// How often each ending was reached, by name.
+    pub endings: BTreeMap<String, u32>,
+    /// Fastest finished game, in seconds.
+    pub best_time: Option<f32>,
+    path: Option<PathBuf>,
+}
+
+impl Profile {
+    pub fn load(path: &std::path::Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        Self::parse(&text)
+    }
+
+    /// Writes the profile back to where it was loaded from, if anywhere.
+    pub fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        if let Err(err) = std::fs::write(path, self.to_text()) {
+            error!("Could not save profile to {}: {}", path.display(), err);
+        }
+    }
+
+    pub fn record_ending(&mut self, ending: &str, time: f32) {
+        *self.endings.entry(ending.to_string()).or_default() += 1;
+        self.best_time = Some(self.best_time.map_or(time, |best| best.min(time)));
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{}\n", FORMAT_HEADER);
+        if let Some(best_time) = self.best_time {
+            let _ = writeln!(text, "best_time {}", best_time);
+        }
+        for (ending, count) in &self.endings {
+            let _ = writeln!(text, "ending {} {}", ending, count);
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines();
+
+        if lines.next() != Some(FORMAT_HEADER) {
+            return Err("not a profile".to_string());
+        }
+
+        let mut profile = Self::default();
+        for (number, line) in lines.enumerate() {
+            let values: Vec<&str> = line.split_whitespace().collect();
+            // Counted from 1, and the header is the first line.
+            let bad = || format!("bad line {}", number + 2);
+
+            match values[..] {
+                ["best_time", time] => profile.best_time = Some(time.parse().map_err(|_| bad())?),
+                ["ending", name, count] => {
+                    profile.endings.insert(name.to_string(), count.parse().map_err(|_| bad())?);
+                }
+                [] => {}
+                _ => return Err(bad()),
+            }
+        }
+
+        Ok(profile)
+    }
+}
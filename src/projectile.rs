@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+use bevy_xpbd_2d::prelude::*;
+use crate::actor::{Dead, EnemyHit, PlayerHit};
+use crate::util::*;
+
+const PROJECTILE_RADIUS: f32 = 3.;
+
+/// Projectiles fired by the player's spells and by bosses.
+pub struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, projectile_hits.in_set(ProjectileSet));
+    }
+}
+
+/// Checks what projectiles hit, in `Update`. Systems firing projectiles run
+/// before it.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProjectileSet;
+
+/// Who a projectile hurts.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ProjectileTarget {
+    Enemies,
+    Player,
+}
+
+#[derive(Component)]
+pub struct Projectile {
+    pub caster: Entity,
+    pub target: ProjectileTarget,
+    pub damage: f32,
+    pub knockback: f32,
+    lifetime: Timer,
+}
+
+impl Projectile {
+    /// A projectile that fizzles out after `lifetime` seconds if it hits
+    /// nothing.
+    pub fn new(caster: Entity, target: ProjectileTarget, damage: f32, knockback: f32, lifetime: f32) -> Self {
+        Self {
+            caster,
+            target,
+            damage,
+            knockback,
+            lifetime: Timer::from_seconds(lifetime, TimerMode::Once),
+        }
+    }
+}
+
+/// A projectile flying off with `velocity` from a caster at `position`. It
+/// starts just outside the caster's `caster_radius`, so it doesn't start
+/// inside its collider.
+pub fn projectile_bundle(projectile: Projectile, position: Vec2, caster_radius: f32, velocity: Vec2) -> impl Bundle {
+    let origin = position + velocity.normalize_or_zero() * (caster_radius + PROJECTILE_RADIUS);
+
+    (
+        projectile,
+        RigidBody::Kinematic,
+        Collider::circle(PROJECTILE_RADIUS),
+        Sensor,
+        LinearVelocity(velocity),
+        SpatialBundle::from_transform(Transform::from_translation(origin.extend(2.))),
+        Name::new("Projectile"),
+    )
+}
+
+// Projectiles hurt the first target they touch and stop at walls.
+fn projectile_hits(
+    mut commands: Commands,
+    mut projectiles: Query<(Entity, &mut Projectile, &Collider, &Position, &LinearVelocity)>,
+    enemies: Query<(), (With<EnemyHitBox>, Without<Dead>)>,
+    players: Query<(), With<PlayerHitBox>>,
+    bodies: Query<&RigidBody>,
+    spatial_query: SpatialQuery,
+    mut enemy_hits: EventWriter<EnemyHit>,
+    mut player_hits: EventWriter<PlayerHit>,
+    time: Res<Time>,
+) {
+    for (entity, mut projectile, collider, position, velocity) in projectiles.iter_mut() {
+        projectile.lifetime.tick(time.delta());
+
+        if projectile.lifetime.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        let filter = SpatialQueryFilter::default().with_excluded_entities([entity, projectile.caster]);
+        let touching = spatial_query.shape_intersections(collider, position.0, 0., filter);
+        let direction = velocity.0.normalize_or_zero();
+
+        let target = match projectile.target {
+            ProjectileTarget::Enemies => touching.iter().copied().find(|hit| enemies.contains(*hit)),
+            ProjectileTarget::Player => touching.iter().copied().find(|hit| players.contains(*hit)),
+        };
+
+        if let Some(target) = target {
+            match projectile.target {
+                ProjectileTarget::Enemies => {
+                    enemy_hits.send(EnemyHit {
+                        enemy: target,
+                        attacker: projectile.caster,
+                        damage: projectile.damage,
+                        direction,
+                        knockback: projectile.knockback,
+                    });
+                }
+                ProjectileTarget::Player => {
+                    player_hits.send(PlayerHit {
+                        attacker: projectile.caster,
+                        damage: projectile.damage,
+                        direction,
+                        knockback: projectile.knockback,
+                    });
+                }
+            }
+            commands.entity(entity).despawn_recursive();
+        } else if touching.iter().any(|hit| bodies.get(*hit).is_ok_and(|body| body.is_static())) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
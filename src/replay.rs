@@ -0,0 +1,337 @@
+use std::{fmt::Write as _, path::PathBuf, time::Duration};
+
+use bevy::{app::AppExit, input::InputSystem, prelude::*, time::TimeUpdateStrategy};
+use crate::aim::AimDirection;
+use crate::rng::GameRng;
+use crate::shop::ShopAction;
+use crate::util::*;
+
+const FORMAT_HEADER: &str = "returner-replay 4";
+pub const DEFAULT_TIMESTEP: f32 = 1. / 60.;
+
+// Every input gameplay reads. A frame stores one bit per entry, keys first.
+const KEYS: [KeyCode; 9] = [
+    KeyCode::KeyW,
+    KeyCode::KeyA,
+    KeyCode::KeyS,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyI,
+    KeyCode::KeyR,
+    KeyCode::KeyQ,
+    KeyCode::ShiftLeft,
+];
+const BUTTONS: [MouseButton; 2] = [MouseButton::Left, MouseButton::Right];
+
+#[derive(Default, Clone)]
+pub enum ReplayMode {
+    #[default]
+    Off,
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+/// Records or replays input. Both modes run on a fixed timestep so the
+/// replay sees exactly the same frames as the recording.
+#[derive(Default)]
+pub struct ReplayPlugin {
+    pub mode: ReplayMode,
+}
+
+impl ReplayPlugin {
+    /// Reads `--record <file>` or `--replay <file>` from the command line.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut args = args.into_iter();
+        let mut mode = ReplayMode::Off;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--record" => mode = ReplayMode::Record(args.next().expect("--record needs a file").into()),
+                "--replay" => mode = ReplayMode::Replay(args.next().expect("--replay needs a file").into()),
+                _ => {}
+            }
+        }
+
+        Self { mode }
+    }
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        match &self.mode {
+            ReplayMode::Off => {
+                app.init_resource::<GameRng>();
+            }
+            ReplayMode::Record(path) => {
+                let rng = GameRng::from_time();
+                app.insert_resource(InputRecorder::new(rng.seed(), DEFAULT_TIMESTEP).saved_to(path.clone()))
+                    .insert_resource(fixed_timestep(DEFAULT_TIMESTEP))
+                    .insert_resource(rng);
+            }
+            ReplayMode::Replay(path) => {
+                let recording = Recording::load(path)
+                    .unwrap_or_else(|err| panic!("could not load replay {}: {}", path.display(), err));
+                app.insert_resource(GameRng::new(recording.seed))
+                    .insert_resource(fixed_timestep(recording.timestep))
+                    .insert_resource(InputReplay::new(recording));
+            }
+        }
+
+        app.add_systems(PreUpdate, replay_input.after(InputSystem).run_if(resource_exists::<InputReplay>))
+            .add_systems(Last, (
+                record_input.run_if(resource_exists::<InputRecorder>),
+                save_recording.run_if(resource_exists::<InputRecorder>),
+            ).chain());
+    }
+}
+
+pub fn fixed_timestep(timestep: f32) -> TimeUpdateStrategy {
+    TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(timestep))
+}
+
+/// Run condition for systems that read live input the replay overrides.
+pub fn not_replaying(replay: Option<Res<InputReplay>>) -> bool {
+    replay.is_none()
+}
+
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct InputFrame {
+    pub pressed: u32,
+    pub just_pressed: u32,
+    pub just_released: u32,
+    pub aim: Vec2,
+    /// Shop UI choices, which go through UI interaction instead of the
+    /// buttons above.
+    pub shop: Vec<ShopAction>,
+}
+
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Recording {
+    pub seed: u64,
+    pub timestep: f32,
+    pub frames: Vec<InputFrame>,
+}
+
+impl Recording {
+    pub fn load(path: &std::path::Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        Self::parse(&text)
+    }
+
+    pub fn save(&self, path: &std::path::Path) -> std::io::Result<()> {
+        std::fs::write(path, self.to_text())
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{}\nseed {}\ntimestep {}\n", FORMAT_HEADER, self.seed, self.timestep);
+        for frame in &self.frames {
+            let shop = match frame.shop.as_slice() {
+                [] => "-".to_string(),
+                actions => actions.iter().map(shop_action_to_text).collect::<Vec<_>>().join(","),
+            };
+            let _ = writeln!(
+                text,
+                "{} {} {} {} {} {}",
+                frame.pressed, frame.just_pressed, frame.just_released, frame.aim.x, frame.aim.y, shop,
+            );
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines();
+
+        if lines.next() != Some(FORMAT_HEADER) {
+            return Err("not a replay file".to_string());
+        }
+
+        let mut header = |name: &str| {
+            lines
+                .next()
+                .and_then(|line| line.strip_prefix(name))
+                .map(|value| value.trim().to_string())
+                .ok_or_else(|| format!("missing {}", name))
+        };
+        let seed = header("seed")?.parse().map_err(|_| "bad seed".to_string())?;
+        let timestep = header("timestep")?.parse().map_err(|_| "bad timestep".to_string())?;
+
+        let mut frames = Vec::new();
+        for (number, line) in lines.enumerate() {
+            let values: Vec<&str> = line.split_whitespace().collect();
+            let bad = || format!("bad frame {}", number);
+            let [pressed, just_pressed, just_released, x, y, shop] = values[..] else {
+                return Err(bad());
+            };
+            let shop = match shop {
+                "-" => Vec::new(),
+                actions => actions
+                    .split(',')
+                    .map(|action| parse_shop_action(action).ok_or_else(bad))
+                    .collect::<Result<_, _>>()?,
+            };
+
+            frames.push(InputFrame {
+                pressed: pressed.parse().map_err(|_| bad())?,
+                just_pressed: just_pressed.parse().map_err(|_| bad())?,
+                just_released: just_released.parse().map_err(|_| bad())?,
+                aim: Vec2::new(x.parse().map_err(|_| bad())?, y.parse().map_err(|_| bad())?),
+                shop,
+            });
+        }
+
+        Ok(Self { seed, timestep, frames })
+    }
+}
+
+fn shop_action_to_text(action: &ShopAction) -> String {
+    match action {
+        ShopAction::Buy(index) => format!("buy:{}", index),
+        ShopAction::Sell(itype) => format!("sell:{:?}", itype),
+        ShopAction::Close => "close".to_string(),
+    }
+}
+
+fn parse_shop_action(text: &str) -> Option<ShopAction> {
+    match text.split_once(':') {
+        Some(("buy", index)) => index.parse().ok().map(ShopAction::Buy),
+        Some(("sell", name)) => ItemType::ALL
+            .into_iter()
+            .find(|itype| format!("{:?}", itype) == name)
+            .map(ShopAction::Sell),
+        None if text == "close" => Some(ShopAction::Close),
+        _ => None,
+    }
+}
+
+#[derive(Resource)]
+pub struct InputRecorder {
+    pub recording: Recording,
+    path: Option<PathBuf>,
+}
+
+impl InputRecorder {
+    pub fn new(seed: u64, timestep: f32) -> Self {
+        Self {
+            recording: Recording { seed, timestep, frames: Vec::new() },
+            path: None,
+        }
+    }
+
+    /// Write the recording to `path` when the app exits.
+    pub fn saved_to(mut self, path: PathBuf) -> Self {
+        self.path = Some(path);
+        self
+    }
+}
+
+#[derive(Resource)]
+pub struct InputReplay {
+    recording: Recording,
+    frame: usize,
+}
+
+impl InputReplay {
+    pub fn new(recording: Recording) -> Self {
+        Self { recording, frame: 0 }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.frame >= self.recording.frames.len()
+    }
+}
+
+fn record_input(
+    mut recorder: ResMut<InputRecorder>,
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    mut shop_actions: EventReader<ShopAction>,
+    aim_q: Query<&AimDirection, With<PlayerMover>>,
+) {
+    let mut frame = InputFrame {
+        aim: aim_q.get_single().map(|aim| aim.0).unwrap_or(Vec2::X),
+        shop: shop_actions.read().copied().collect(),
+        ..Default::default()
+    };
+
+    for (bit, key) in KEYS.iter().enumerate() {
+        set_bits(&mut frame, bit, keys.pressed(*key), keys.just_pressed(*key), keys.just_released(*key));
+    }
+    for (i, button) in BUTTONS.iter().enumerate() {
+        let bit = KEYS.len() + i;
+        set_bits(&mut frame, bit, buttons.pressed(*button), buttons.just_pressed(*button), buttons.just_released(*button));
+    }
+
+    recorder.recording.frames.push(frame);
+}
+
+fn set_bits(frame: &mut InputFrame, bit: usize, pressed: bool, just_pressed: bool, just_released: bool) {
+    frame.pressed |= (pressed as u32) << bit;
+    frame.just_pressed |= (just_pressed as u32) << bit;
+    frame.just_released |= (just_released as u32) << bit;
+}
+
+fn save_recording(recorder: Res<InputRecorder>, mut exit: EventReader<AppExit>) {
+    if exit.read().next().is_none() {
+        return;
+    }
+    let Some(path) = &recorder.path else {
+        return;
+    };
+
+    match recorder.recording.save(path) {
+        Ok(()) => info!("Saved {} frames of input to {}", recorder.recording.frames.len(), path.display()),
+        Err(err) => error!("Could not save input recording to {}: {}", path.display(), err),
+    }
+}
+
+fn replay_input(
+    mut commands: Commands,
+    mut replay: ResMut<InputReplay>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut buttons: ResMut<ButtonInput<MouseButton>>,
+    mut shop_actions: EventWriter<ShopAction>,
+    mut aim_q: Query<&mut AimDirection, With<PlayerMover>>,
+) {
+    let Some(frame) = replay.recording.frames.get(replay.frame).cloned() else {
+        info!("Replay finished after {} frames", replay.frame);
+        commands.remove_resource::<InputReplay>();
+        return;
+    };
+    replay.frame += 1;
+
+    for (bit, key) in KEYS.iter().enumerate() {
+        replay_button(&mut keys, *key, &frame, bit);
+    }
+    for (i, button) in BUTTONS.iter().enumerate() {
+        replay_button(&mut buttons, *button, &frame, KEYS.len() + i);
+    }
+
+    if let Ok(mut aim) = aim_q.get_single_mut() {
+        aim.0 = frame.aim;
+    }
+    shop_actions.send_batch(frame.shop);
+}
+
+// Rebuilds the exact pressed/just pressed/just released state of one
+// button, whatever the real device is doing.
+fn replay_button<T>(input: &mut ButtonInput<T>, button: T, frame: &InputFrame, bit: usize)
+where
+    T: Copy + Eq + std::hash::Hash + Send + Sync + 'static,
+{
+    let pressed = frame.pressed >> bit & 1 == 1;
+    let just_pressed = frame.just_pressed >> bit & 1 == 1;
+    let just_released = frame.just_released >> bit & 1 == 1;
+
+    input.reset(button);
+
+    if just_released {
+        input.press(button);
+        input.release(button);
+    }
+    if pressed {
+        input.press(button);
+    }
+    if !just_pressed {
+        input.clear_just_pressed(button);
+    }
+}
@@ -0,0 +1,103 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    ecs::system::SystemParam,
+    prelude::*,
+    utils::BoxedFuture,
+};
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+/// Game data read from one RON file, like the loot tables or the bosses.
+pub trait RonAsset: Asset + DeserializeOwned {
+    /// The file loaded at startup.
+    const PATH: &'static str;
+    /// What the loader claims, e.g. `["loot.ron"]`.
+    const EXTENSIONS: &'static [&'static str];
+}
+
+/// Registers a `RonAsset` and loads its file at startup. Look it up with
+/// `RonAssets`.
+pub struct RonAssetPlugin<T>(PhantomData<T>);
+
+impl<T> Default for RonAssetPlugin<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: RonAsset> Plugin for RonAssetPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<T>()
+            .init_asset_loader::<RonAssetLoader<T>>()
+            .add_systems(Startup, load::<T>);
+    }
+}
+
+pub struct RonAssetLoader<T>(PhantomData<T>);
+
+impl<T> Default for RonAssetLoader<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RonAssetLoaderError {
+    #[error("Could not read {0}: {1}")]
+    Io(&'static str, std::io::Error),
+    #[error("Could not parse {0}: {1}")]
+    Ron(&'static str, ron::error::SpannedError),
+}
+
+impl<T: RonAsset> AssetLoader for RonAssetLoader<T> {
+    type Asset = T;
+    type Settings = ();
+    type Error = RonAssetLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader
+                .read_to_end(&mut bytes)
+                .await
+                .map_err(|error| RonAssetLoaderError::Io(T::PATH, error))?;
+            ron::de::from_bytes(&bytes).map_err(|error| RonAssetLoaderError::Ron(T::PATH, error))
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        T::EXTENSIONS
+    }
+}
+
+/// Handle to the loaded file of a `RonAsset`.
+#[derive(Resource)]
+pub struct RonHandle<T: RonAsset>(pub Handle<T>);
+
+fn load<T: RonAsset>(mut commands: Commands, assets: Res<AssetServer>) {
+    commands.insert_resource(RonHandle::<T>(assets.load(T::PATH)));
+}
+
+/// The contents of a `RonAsset` file, once it is loaded.
+#[derive(SystemParam)]
+pub struct RonAssets<'w, T: RonAsset> {
+    handle: Option<Res<'w, RonHandle<T>>>,
+    assets: Res<'w, Assets<T>>,
+}
+
+impl<T: RonAsset> RonAssets<'_, T> {
+    pub fn is_loaded(&self) -> bool {
+        self.get().is_some()
+    }
+
+    pub fn get(&self) -> Option<&T> {
+        self.handle.as_ref().and_then(|handle| self.assets.get(&handle.0))
+    }
+}
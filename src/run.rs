@@ -0,0 +1,349 @@
+use bevy::prelude::*;
+use crate::actor::{Dead, Enemy, Player};
+use crate::ending::{EndingSequence, EndingSet, RunStats};
+use crate::inventory::Inventory;
+use crate::level::{LoadLevel, START_LEVEL};
+use crate::rng::GameRng;
+use crate::spawner::SpawnerState;
+
+pub struct RunPlugin;
+
+impl Plugin for RunPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunConfig>()
+            .register_type::<RunConfig>()
+            .init_resource::<Run>()
+            .add_event::<RunFinished>()
+            .add_event::<RunStarted>()
+            // New enemies are toughened before any of their systems run.
+            .add_systems(PreUpdate, apply_enemy_modifiers)
+            .add_systems(Update, (
+                init_run,
+                start_next_run,
+            ).chain())
+            // Deaths and spares despawn at the end of `Update`, and a run
+            // that reaches an ending doesn't get a summary.
+            .add_systems(PostUpdate, finish_run.after(EndingSet));
+    }
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct RunConfig {
+    pub carry_over: CarryOver,
+    /// Every run after the first gets this many more modifiers than the one
+    /// before, up to one of each.
+    pub modifiers_per_run: usize,
+    /// What the modifiers multiply.
+    pub hardy_hp: f32,
+    pub bold_fear: f32,
+    pub frail_hp: f32,
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        Self {
+            carry_over: CarryOver::default(),
+            modifiers_per_run: 1,
+            hardy_hp: 1.5,
+            bold_fear: 0.5,
+            frail_hp: 0.75,
+        }
+    }
+}
+
+/// Which `Player` fields are kept from one run to the next. The rest go back
+/// to what they were when the game started.
+#[derive(Reflect, Clone, Copy, Debug)]
+pub struct CarryOver {
+    pub compleation: bool,
+    /// `sword_skill` and `run_skill`.
+    pub skills: bool,
+    pub hp: bool,
+    pub mp: bool,
+    pub inventory: bool,
+    /// `killed` and `spared`, which the ending is picked from.
+    pub tallies: bool,
+}
+
+impl Default for CarryOver {
+    fn default() -> Self {
+        Self {
+            compleation: true,
+            skills: true,
+            hp: false,
+            mp: false,
+            inventory: true,
+            tallies: true,
+        }
+    }
+}
+
+/// Makes a run harder than the one before.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RunModifier {
+    /// Enemies have more HP.
+    Hardy,
+    /// Enemies start out less afraid.
+    Bold,
+    /// The player starts the run with less HP.
+    Frail,
+}
+
+impl RunModifier {
+    pub const ALL: [RunModifier; 3] = [RunModifier::Hardy, RunModifier::Bold, RunModifier::Frail];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RunModifier::Hardy => "Hardy",
+            RunModifier::Bold => "Bold",
+            RunModifier::Frail => "Frail",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum RunPhase {
+    #[default]
+    Playing,
+    /// The level is cleared, the summary is up until the player starts the
+    /// next run.
+    Summary,
+}
+
+/// What happened during one run.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct RunSummary {
+    pub run: u32,
+    pub kills: i32,
+    pub spares: i32,
+    pub items: i32,
+    pub time: f32,
+    pub compleation: f32,
+}
+
+/// Which run this is and how it is going. The level is played again every
+/// run, the global `Player` keeps what `CarryOver` says.
+#[derive(Resource, Debug)]
+pub struct Run {
+    /// Counts from 1.
+    pub number: u32,
+    pub phase: RunPhase,
+    pub modifiers: Vec<RunModifier>,
+    /// Rolled when a run finishes, so the summary can show them.
+    pub next_modifiers: Vec<RunModifier>,
+    /// Of the run that just finished, while the summary is up.
+    pub summary: Option<RunSummary>,
+    fresh: Option<PlayerStart>,
+    start: RunTally,
+}
+
+impl Default for Run {
+    fn default() -> Self {
+        Self {
+            number: 1,
+            phase: RunPhase::Playing,
+            modifiers: Vec::new(),
+            next_modifiers: Vec::new(),
+            summary: None,
+            fresh: None,
+            start: RunTally::default(),
+        }
+    }
+}
+
+/// The `Player` fields a new run can reset, as the game started.
+#[derive(Clone, Copy, Debug)]
+struct PlayerStart {
+    hp: f32,
+    mp: f32,
+    sword_skill: f32,
+    run_skill: f32,
+    compleation: f32,
+}
+
+// Running totals, a run is the difference between its end and its start.
+#[derive(Clone, Copy, Default, Debug)]
+struct RunTally {
+    killed: i32,
+    spared: i32,
+    items: i32,
+    time: f32,
+    compleation: f32,
+}
+
+impl RunTally {
+    fn of(player: &Player, stats: &RunStats) -> Self {
+        Self {
+            killed: player.killed,
+            spared: player.spared,
+            items: stats.items_collected,
+            time: stats.time,
+            compleation: player.compleation,
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct RunFinished(pub RunSummary);
+
+/// Sent along with the `LoadLevel` for the new run's fresh level.
+#[derive(Event)]
+pub struct RunStarted {
+    pub number: u32,
+}
+
+fn init_run(
+    mut run: ResMut<Run>,
+    players: Query<&Player, Added<Player>>,
+    stats: Res<RunStats>,
+) {
+    let Some(player) = players.iter().next() else {
+        return;
+    };
+    if run.fresh.is_some() {
+        return;
+    }
+
+    run.fresh = Some(PlayerStart {
+        hp: player.hp,
+        mp: player.mp,
+        sword_skill: player.sword_skill,
+        run_skill: player.run_skill,
+        compleation: player.compleation,
+    });
+    run.start = RunTally::of(player, &stats);
+}
+
+// Modifiers are picked at random without repeats, more of them every run.
+fn roll_modifiers(number: u32, config: &RunConfig, rng: &mut GameRng) -> Vec<RunModifier> {
+    let count = (number.saturating_sub(1) as usize * config.modifiers_per_run).min(RunModifier::ALL.len());
+    let mut pool = RunModifier::ALL.to_vec();
+
+    (0..count)
+        .map(|_| pool.remove(rng.range_i32(0, pool.len() as i32 - 1) as usize))
+        .collect()
+}
+
+// A run is over once nobody is left to fight, like the old "kill everyone,
+// press R" loop. Bosses are optional and don't count, see `boss_bundle`.
+fn finish_run(
+    mut run: ResMut<Run>,
+    sequence: Option<Res<EndingSequence>>,
+    player_q: Query<&Player>,
+    enemies: Query<(), (With<Enemy>, Without<Dead>)>,
+    spawners: Query<&SpawnerState>,
+    stats: Res<RunStats>,
+    config: Res<RunConfig>,
+    mut rng: ResMut<GameRng>,
+    mut finished: EventWriter<RunFinished>,
+) {
+    if run.phase != RunPhase::Playing || sequence.is_some() {
+        return;
+    }
+    let Ok(player) = player_q.get_single() else {
+        return;
+    };
+    if !enemies.is_empty() || spawners.iter().any(SpawnerState::is_running) {
+        return;
+    }
+
+    // Nobody was dealt with yet, the level can't have been played.
+    let now = RunTally::of(player, &stats);
+    if now.killed + now.spared == run.start.killed + run.start.spared {
+        return;
+    }
+
+    let summary = RunSummary {
+        run: run.number,
+        kills: now.killed - run.start.killed,
+        spares: now.spared - run.start.spared,
+        items: now.items - run.start.items,
+        time: now.time - run.start.time,
+        compleation: now.compleation - run.start.compleation,
+    };
+    info!("Finished run {}: {:?}", run.number, summary);
+
+    run.next_modifiers = roll_modifiers(run.number + 1, &config, &mut rng);
+    run.summary = Some(summary);
+    run.phase = RunPhase::Summary;
+    finished.send(RunFinished(summary));
+}
+
+// R on the summary screen starts the next run.
+fn start_next_run(
+    input: Res<ButtonInput<KeyCode>>,
+    mut run: ResMut<Run>,
+    mut player_q: Query<(&mut Player, &mut Inventory)>,
+    stats: Res<RunStats>,
+    config: Res<RunConfig>,
+    mut started: EventWriter<RunStarted>,
+    mut levels: EventWriter<LoadLevel>,
+) {
+    if run.phase != RunPhase::Summary || !input.just_released(KeyCode::KeyR) {
+        return;
+    }
+    let Ok((mut player, mut inventory)) = player_q.get_single_mut() else {
+        return;
+    };
+    let Some(fresh) = run.fresh else {
+        return;
+    };
+
+    let carry = config.carry_over;
+    if !carry.compleation {
+        player.compleation = fresh.compleation;
+    }
+    if !carry.skills {
+        player.sword_skill = fresh.sword_skill;
+        player.run_skill = fresh.run_skill;
+    }
+    if !carry.hp {
+        player.hp = fresh.hp;
+    }
+    if !carry.mp {
+        player.mp = fresh.mp;
+    }
+    if !carry.inventory {
+        *inventory = Inventory::from(&player.starting_items);
+    }
+    if !carry.tallies {
+        player.killed = 0;
+        player.spared = 0;
+    }
+
+    let modifiers = std::mem::take(&mut run.next_modifiers);
+    if modifiers.contains(&RunModifier::Frail) {
+        player.hp = player.hp.min(fresh.hp * config.frail_hp);
+    }
+
+    run.number += 1;
+    run.modifiers = modifiers;
+    run.summary = None;
+    run.start = RunTally::of(&player, &stats);
+    run.phase = RunPhase::Playing;
+    info!("Starting run {} with {:?}", run.number, run.modifiers);
+
+    started.send(RunStarted { number: run.number });
+    levels.send(LoadLevel { identifier: START_LEVEL.to_string() });
+}
+
+// Covers enemies placed in LDtk and the ones spawners send alike.
+fn apply_enemy_modifiers(
+    mut enemies: Query<&mut Enemy, Added<Enemy>>,
+    run: Res<Run>,
+    config: Res<RunConfig>,
+) {
+    if run.modifiers.is_empty() {
+        return;
+    }
+
+    for mut enemy in enemies.iter_mut() {
+        if run.modifiers.contains(&RunModifier::Hardy) {
+            enemy.hp *= config.hardy_hp;
+        }
+        if run.modifiers.contains(&RunModifier::Bold) {
+            enemy.fear *= config.bold_fear;
+        }
+    }
+}
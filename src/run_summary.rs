@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+use crate::credits::BACKGROUND_COLOR;
+use crate::run::{Run, RunModifier, RunPhase};
+
+/// Shows how the last run went between two runs.
+pub struct RunSummaryPlugin;
+
+impl Plugin for RunSummaryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (spawn_summary, despawn_summary));
+    }
+}
+
+#[derive(Component)]
+struct SummaryScreen;
+
+fn modifier_names(modifiers: &[RunModifier]) -> String {
+    match modifiers {
+        [] => "none".to_string(),
+        modifiers => modifiers.iter().map(RunModifier::name).collect::<Vec<_>>().join(", "),
+    }
+}
+
+fn spawn_summary(
+    mut commands: Commands,
+    run: Res<Run>,
+    screen_q: Query<(), With<SummaryScreen>>,
+) {
+    if run.phase != RunPhase::Summary || !screen_q.is_empty() {
+        return;
+    }
+    let Some(summary) = run.summary else {
+        return;
+    };
+
+    let lines = [
+        format!("Run {} cleared", summary.run),
+        format!("Killed {}, spared {}", summary.kills, summary.spares),
+        format!("Collected {} items in {:.0}s", summary.items, summary.time),
+        format!("Compleation +{:.0}", summary.compleation),
+        String::new(),
+        format!("Next run: {}", modifier_names(&run.next_modifiers)),
+        String::new(),
+        "Press R to continue".to_string(),
+    ];
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(6.),
+                    ..Default::default()
+                },
+                background_color: BACKGROUND_COLOR.into(),
+                ..Default::default()
+            },
+            SummaryScreen,
+        ))
+        .with_children(|screen| {
+            for line in lines {
+                screen.spawn(TextBundle::from_section(line, TextStyle {
+                    font_size: 16.,
+                    ..Default::default()
+                }));
+            }
+        });
+}
+
+fn despawn_summary(
+    mut commands: Commands,
+    run: Res<Run>,
+    screen_q: Query<Entity, With<SummaryScreen>>,
+) {
+    if run.phase == RunPhase::Summary {
+        return;
+    }
+
+    for entity in screen_q.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,51 @@
+use bevy::{prelude::*, utils::{HashMap, HashSet}};
+use bevy_asepritesheet::prelude::*;
+
+pub struct SheetsPlugin;
+
+impl Plugin for SheetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpritesheetRegistry>();
+    }
+}
+
+/// Loads every spritesheet once and hands out shared handles, so spawning
+/// a hundred enemies doesn't parse `enemy.json` a hundred times.
+#[derive(Resource, Default)]
+pub struct SpritesheetRegistry {
+    sheets: HashMap<String, Handle<Spritesheet>>,
+    formatted: HashSet<(AssetId<Spritesheet>, String)>,
+}
+
+impl SpritesheetRegistry {
+    pub fn get_or_load(
+        &mut self,
+        commands: &mut Commands,
+        assets: &AssetServer,
+        path: &str,
+    ) -> Handle<Spritesheet> {
+        self.sheets
+            .entry(path.to_string())
+            .or_insert_with(|| {
+                load_spritesheet_then(
+                    commands,
+                    assets,
+                    path,
+                    bevy::sprite::Anchor::Center,
+                    |_sheet| {
+                        info!("Spritesheet finished loading!");
+                    },
+                )
+            })
+            .clone()
+    }
+
+    /// Whether the end action of `tag` was already set on `sheet`.
+    pub fn is_formatted(&self, sheet: AssetId<Spritesheet>, tag: &str) -> bool {
+        self.formatted.contains(&(sheet, tag.to_string()))
+    }
+
+    pub fn mark_formatted(&mut self, sheet: AssetId<Spritesheet>, tag: &str) {
+        self.formatted.insert((sheet, tag.to_string()));
+    }
+}
@@ -0,0 +1,211 @@
+use bevy::prelude::*;
+use bevy_entitiles_derive::LdtkEntity;
+use bevy_yarnspinner::prelude::*;
+use crate::actor::Player;
+use crate::interact::{Interact, InteractSet, Interactable};
+use crate::inventory::Inventory;
+use crate::util::*;
+
+const TALK_RANGE: f32 = 24.;
+// Merchants buy items back for this much of what they would sell them for.
+const SELL_RATIO: f32 = 0.5;
+
+pub struct ShopPlugin;
+
+impl Plugin for ShopPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Shop>()
+            .register_type::<Merchant>()
+            .add_event::<ShopAction>()
+            .add_systems(Update, mark_merchants.before(InteractSet))
+            .add_systems(Update, (
+                register_shop_commands,
+                talk_to_merchants,
+                shop_actions,
+                close_distant_shop,
+            ).chain().after(InteractSet));
+    }
+}
+
+#[derive(Component, LdtkEntity, Reflect)]
+pub struct Merchant {
+    pub name: String,
+    pub stock: ItemTypeVec,
+    /// Price of each item in `stock`, items without one sell for their
+    /// `value`.
+    pub prices: Vec<i32>,
+}
+
+impl Merchant {
+    /// The item at `index` of the stock and what it costs.
+    pub fn offer(&self, index: usize) -> Option<(ItemType, i32)> {
+        let itype = *self.stock.0.get(index)?;
+        let price = self.prices.get(index).copied().unwrap_or(itype.value());
+
+        Some((itype, price))
+    }
+
+    pub fn offers(&self) -> impl Iterator<Item = (ItemType, i32)> + '_ {
+        (0..self.stock.0.len()).filter_map(|index| self.offer(index))
+    }
+
+    /// What the merchant pays for an item, `None` for things they won't buy.
+    pub fn buy_price(&self, itype: ItemType) -> Option<i32> {
+        if itype == ItemType::Coins {
+            return None;
+        }
+
+        let price = self
+            .offers()
+            .find(|(offer, _)| *offer == itype)
+            .map_or(itype.value(), |(_, price)| price);
+
+        Some(((price as f32 * SELL_RATIO) as i32).max(1))
+    }
+}
+
+impl ItemType {
+    /// Base worth in Coins, for merchants that don't set their own price.
+    pub fn value(&self) -> i32 {
+        match self {
+            ItemType::Coins => 1,
+            ItemType::Bandage => 4,
+            ItemType::Key => 10,
+            ItemType::Scroll => 12,
+            ItemType::Soul => 15,
+            ItemType::Sword => 25,
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Shop {
+    /// The merchant whose shop is open.
+    pub open: Option<Entity>,
+    talking_to: Option<Entity>,
+}
+
+pub fn shop_closed(shop: Res<Shop>) -> bool {
+    shop.open.is_none()
+}
+
+/// A choice made in the shop UI. Recorded with the input, so replays make
+/// the same purchases.
+#[derive(Event, Clone, Copy, PartialEq, Debug)]
+pub enum ShopAction {
+    /// Buys one of the item at this index of the merchant's stock.
+    Buy(usize),
+    Sell(ItemType),
+    Close,
+}
+
+// Lets merchant dialogue open the shop with `<<open_shop>>`.
+fn register_shop_commands(mut runners: Query<&mut DialogueRunner, Added<DialogueRunner>>) {
+    for mut runner in runners.iter_mut() {
+        runner.commands_mut().add_command("open_shop", open_shop_command);
+    }
+}
+
+fn open_shop_command(_: In<()>, mut shop: ResMut<Shop>) {
+    shop.open = shop.talking_to;
+}
+
+// Merchants are talked to with E.
+fn mark_merchants(mut commands: Commands, merchants: Query<Entity, Added<Merchant>>) {
+    for entity in merchants.iter() {
+        commands.entity(entity).insert(Interactable { range: TALK_RANGE });
+    }
+}
+
+fn talk_to_merchants(
+    mut interactions: EventReader<Interact>,
+    mut shop: ResMut<Shop>,
+    merchants: Query<&Merchant>,
+    mut runners: Query<&mut DialogueRunner>,
+) {
+    for interaction in interactions.read() {
+        let Ok(merchant) = merchants.get(interaction.target) else {
+            continue;
+        };
+        shop.talking_to = Some(interaction.target);
+
+        // Without dialogue (e.g. headless) skip straight to the shop.
+        let Ok(mut runner) = runners.get_single_mut() else {
+            shop.open = Some(interaction.target);
+            continue;
+        };
+        let _ = runner
+            .variable_storage_mut()
+            .set("$merchant".to_string(), YarnValue::String(merchant.name.clone()));
+        if runner.current_node().is_some() {
+            runner.stop();
+        }
+        runner.start_node("Merchant");
+    }
+}
+
+fn shop_actions(
+    mut actions: EventReader<ShopAction>,
+    mut shop: ResMut<Shop>,
+    merchants: Query<&Merchant>,
+    mut inventory_q: Query<&mut Inventory, With<Player>>,
+) {
+    let Ok(mut inventory) = inventory_q.get_single_mut() else {
+        return;
+    };
+
+    for action in actions.read() {
+        let Some(merchant) = shop.open.and_then(|entity| merchants.get(entity).ok()) else {
+            continue;
+        };
+
+        match *action {
+            ShopAction::Buy(index) => {
+                let Some((itype, price)) = merchant.offer(index) else {
+                    continue;
+                };
+                if !inventory.remove(ItemType::Coins, price) {
+                    continue;
+                }
+                // No room for it, give the money back.
+                if inventory.add(itype, 1) > 0 {
+                    inventory.add(ItemType::Coins, price);
+                }
+            }
+            ShopAction::Sell(itype) => {
+                let Some(price) = merchant.buy_price(itype) else {
+                    continue;
+                };
+                if !inventory.remove(itype, 1) {
+                    continue;
+                }
+                let unpaid = inventory.add(ItemType::Coins, price);
+                if unpaid > 0 {
+                    inventory.remove(ItemType::Coins, price - unpaid);
+                    inventory.add(itype, 1);
+                }
+            }
+            ShopAction::Close => shop.open = None,
+        }
+    }
+}
+
+fn close_distant_shop(
+    mut shop: ResMut<Shop>,
+    merchants: Query<&GlobalTransform, With<Merchant>>,
+    player_q: Query<&GlobalTransform, With<PlayerMover>>,
+) {
+    let Some(entity) = shop.open else {
+        return;
+    };
+    let Ok(player_t) = player_q.get_single() else {
+        return;
+    };
+
+    let too_far = merchants.get(entity).map_or(true, |merchant_t| {
+        merchant_t.translation().distance(player_t.translation()) > TALK_RANGE * 2.
+    });
+    if too_far {
+        shop.open = None;
+    }
+}
@@ -0,0 +1,133 @@
+use bevy::{prelude::*, ui::UiSystem};
+use crate::actor::Player;
+use crate::inventory::Inventory;
+use crate::replay::not_replaying;
+use crate::shop::{Merchant, Shop, ShopAction};
+use crate::util::*;
+
+const PANEL_COLOR: Color = Color::rgba(0.1, 0.1, 0.15, 0.9);
+const BUTTON_COLOR: Color = Color::rgb(0.2, 0.2, 0.3);
+const BUTTON_HOVER_COLOR: Color = Color::rgb(0.3, 0.3, 0.45);
+
+/// The buy/sell panel of the merchant whose shop is open.
+pub struct ShopUiPlugin;
+
+impl Plugin for ShopUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, rebuild_shop_ui)
+            .add_systems(PreUpdate, shop_buttons.after(UiSystem::Focus).run_if(not_replaying));
+    }
+}
+
+#[derive(Component)]
+struct ShopUi;
+
+#[derive(Component)]
+struct ShopButton(ShopAction);
+
+// The panel is small, so it is simply rebuilt whenever the shop or the
+// player's inventory changes.
+fn rebuild_shop_ui(
+    mut commands: Commands,
+    shop: Res<Shop>,
+    merchants: Query<&Merchant>,
+    inventory_q: Query<Ref<Inventory>, With<Player>>,
+    ui_q: Query<Entity, With<ShopUi>>,
+) {
+    let inventory = inventory_q.get_single().ok();
+    let inventory_changed = inventory.as_ref().is_some_and(|inventory| inventory.is_changed());
+    if !shop.is_changed() && !inventory_changed {
+        return;
+    }
+
+    for entity in ui_q.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let Some(merchant) = shop.open.and_then(|entity| merchants.get(entity).ok()) else {
+        return;
+    };
+    let Some(inventory) = inventory else {
+        return;
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(16.),
+                    right: Val::Px(16.),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(8.)),
+                    row_gap: Val::Px(4.),
+                    ..Default::default()
+                },
+                background_color: PANEL_COLOR.into(),
+                ..Default::default()
+            },
+            ShopUi,
+        ))
+        .with_children(|panel| {
+            label(panel, format!("{}'s shop", merchant.name));
+            label(panel, format!("Coins: {}", inventory.count(ItemType::Coins)));
+
+            label(panel, "Buy".to_string());
+            for (index, (itype, price)) in merchant.offers().enumerate() {
+                button(panel, format!("{:?} - {}c", itype, price), ShopAction::Buy(index));
+            }
+
+            label(panel, "Sell".to_string());
+            for stack in inventory.stacks() {
+                if let Some(price) = merchant.buy_price(stack.itype) {
+                    button(
+                        panel,
+                        format!("{:?} x{} - {}c", stack.itype, stack.count, price),
+                        ShopAction::Sell(stack.itype),
+                    );
+                }
+            }
+
+            button(panel, "Close".to_string(), ShopAction::Close);
+        });
+}
+
+fn label(parent: &mut ChildBuilder, text: String) {
+    parent.spawn(TextBundle::from_section(text, TextStyle {
+        font_size: 16.,
+        ..Default::default()
+    }));
+}
+
+fn button(parent: &mut ChildBuilder, text: String, action: ShopAction) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    padding: UiRect::axes(Val::Px(6.), Val::Px(2.)),
+                    ..Default::default()
+                },
+                background_color: BUTTON_COLOR.into(),
+                ..Default::default()
+            },
+            ShopButton(action),
+        ))
+        .with_children(|button| label(button, text));
+}
+
+// Runs right after bevy_ui picks up clicks, so a choice reaches the shop in
+// the same frame whether it was clicked or is being replayed.
+fn shop_buttons(
+    mut buttons: Query<(&Interaction, &ShopButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut actions: EventWriter<ShopAction>,
+) {
+    for (interaction, button, mut color) in buttons.iter_mut() {
+        match interaction {
+            Interaction::Pressed => {
+                actions.send(button.0);
+            }
+            Interaction::Hovered => *color = BUTTON_HOVER_COLOR.into(),
+            Interaction::None => *color = BUTTON_COLOR.into(),
+        }
+    }
+}
@@ -0,0 +1,333 @@
+use bevy::{
+    ecs::system::{EntityCommands, SystemParam},
+    prelude::*,
+    utils::HashMap,
+};
+use bevy_entitiles::ldtk::{
+    json::{field::FieldInstance, level::EntityInstance},
+    resources::LdtkAssets,
+};
+use bevy_entitiles_derive::LdtkEntity;
+use serde::Deserialize;
+use crate::actor::{enemy_bundle, Dead, Enemy, Player};
+use crate::rng::GameRng;
+use crate::ron_asset::{RonAsset, RonAssetPlugin, RonAssets, RonHandle};
+use crate::util::*;
+
+pub struct SpawnerPlugin;
+
+impl Plugin for SpawnerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(RonAssetPlugin::<EnemyTemplateSet>::default())
+            .init_resource::<WaveConfig>()
+            .register_type::<WaveConfig>()
+            .init_resource::<WaveDirector>()
+            .add_event::<WaveCleared>()
+            .add_systems(Update, (
+                direct_waves,
+                trigger_spawners,
+                run_spawners,
+                despawn_orphans,
+            ).chain());
+    }
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct WaveConfig {
+    /// `Player::compleation` per difficulty level, the same steps the enemy
+    /// dialogue goes up in.
+    pub compleation_per_level: f32,
+    pub max_level: u32,
+    /// Added to every wave per difficulty level.
+    pub extra_enemies: i32,
+    /// Spawn intervals are multiplied by this once per difficulty level.
+    pub interval_factor: f32,
+    /// Share of HP spawned enemies gain per difficulty level.
+    pub hp_bonus: f32,
+}
+
+impl Default for WaveConfig {
+    fn default() -> Self {
+        Self {
+            compleation_per_level: 25.,
+            max_level: 3,
+            extra_enemies: 1,
+            interval_factor: 0.8,
+            hp_bonus: 0.25,
+        }
+    }
+}
+
+/// Scales waves with how far the player got. Waves keep the difficulty they
+/// started with.
+#[derive(Resource, Default, Debug)]
+pub struct WaveDirector {
+    pub level: u32,
+    pub waves_started: u32,
+}
+
+impl WaveDirector {
+    /// The wave `spawner` sends at the current difficulty.
+    pub fn wave(&self, spawner: &Spawner, config: &WaveConfig) -> Wave {
+        let level = self.level as i32;
+
+        Wave {
+            remaining: spawner.count + config.extra_enemies * level,
+            interval: spawner.interval * config.interval_factor.powi(level),
+            hp_factor: 1. + config.hp_bonus * level as f32,
+            cooldown: Timer::from_seconds(0., TimerMode::Once),
+        }
+    }
+}
+
+/// Every enemy template of the game by name. Loaded from a `.enemy.ron`
+/// file, LDtk `Spawner` entities refer to the templates by name.
+#[derive(Asset, TypePath, Deserialize, Default)]
+pub struct EnemyTemplateSet {
+    pub templates: HashMap<String, EnemyTemplate>,
+}
+
+impl RonAsset for EnemyTemplateSet {
+    const PATH: &'static str = "enemies/templates.enemy.ron";
+    const EXTENSIONS: &'static [&'static str] = &["enemy.ron"];
+}
+
+/// The LDtk `Enemy` fields a spawned enemy starts with.
+#[derive(Deserialize, Clone)]
+pub struct EnemyTemplate {
+    pub hp: f32,
+    pub attack: f32,
+    pub fear: f32,
+    /// Spawned enemies get one of these names at random.
+    pub names: Vec<String>,
+    #[serde(default)]
+    pub sprite: String,
+    #[serde(default)]
+    pub loot: String,
+    #[serde(default)]
+    pub inventory: Vec<ItemType>,
+}
+
+pub type EnemyTemplates = RonHandle<EnemyTemplateSet>;
+
+/// Looks enemy templates up by name once they are loaded.
+#[derive(SystemParam)]
+pub struct Templates<'w> {
+    set: RonAssets<'w, EnemyTemplateSet>,
+}
+
+impl Templates<'_> {
+    pub fn is_loaded(&self) -> bool {
+        self.set.is_loaded()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&EnemyTemplate> {
+        self.set.get().and_then(|set| set.templates.get(name))
+    }
+}
+
+/// An LDtk `Spawner`. The entity's size in LDtk is the area that starts
+/// its wave, and where its enemies appear.
+#[derive(Component, LdtkEntity, Default, Reflect)]
+#[callback(spawner_spawn)]
+pub struct Spawner {
+    /// Name of the `EnemyTemplate` it spawns.
+    pub template: String,
+    /// Enemies in a wave, before the wave director adds more.
+    pub count: i32,
+    /// Seconds between spawns.
+    pub interval: f32,
+    /// Never more than this many of its enemies alive at once, 0 for no
+    /// limit.
+    pub max_alive: i32,
+}
+
+fn spawner_spawn(
+    commands: &mut EntityCommands,
+    entity_instance: &EntityInstance,
+    _fields: &HashMap<String, FieldInstance>,
+    _asset_server: &AssetServer,
+    _ldtk_assets: &LdtkAssets,
+) {
+    let pos = Vec3::new(entity_instance.local_pos[0] as f32, -entity_instance.local_pos[1] as f32, 0.);
+    let size = Vec2::new(entity_instance.width as f32, entity_instance.height as f32);
+
+    commands.insert((
+        Name::new("Spawner"),
+        SpatialBundle::from_transform(Transform::from_translation(pos)),
+        spawner_bundle(size),
+    ));
+}
+
+/// Runtime components of a `Spawner` covering an area of `size`.
+fn spawner_bundle(size: Vec2) -> impl Bundle {
+    (SpawnerArea { half_size: size / 2. }, SpawnerState::Waiting)
+}
+
+/// Where a spawner's wave is triggered and its enemies appear, centred on
+/// the spawner.
+#[derive(Component)]
+pub struct SpawnerArea {
+    pub half_size: Vec2,
+}
+
+impl SpawnerArea {
+    pub fn contains(&self, centre: Vec2, point: Vec2) -> bool {
+        let offset = (point - centre).abs();
+        offset.x <= self.half_size.x && offset.y <= self.half_size.y
+    }
+}
+
+#[derive(Component, Debug)]
+pub enum SpawnerState {
+    /// Waiting for the player to walk in.
+    Waiting,
+    Running(Wave),
+    /// Its wave was beaten, a spawner only sends one per level load.
+    Cleared,
+}
+
+impl SpawnerState {
+    pub fn is_running(&self) -> bool {
+        matches!(self, SpawnerState::Running(_))
+    }
+}
+
+#[derive(Debug)]
+pub struct Wave {
+    /// Enemies still to be spawned.
+    pub remaining: i32,
+    pub interval: f32,
+    pub hp_factor: f32,
+    cooldown: Timer,
+}
+
+/// Which spawner an enemy came from.
+#[derive(Component)]
+pub struct SpawnedBy(pub Entity);
+
+#[derive(Event)]
+pub struct WaveCleared {
+    pub spawner: Entity,
+}
+
+fn direct_waves(
+    mut director: ResMut<WaveDirector>,
+    player_q: Query<&Player>,
+    config: Res<WaveConfig>,
+) {
+    let Ok(player) = player_q.get_single() else {
+        return;
+    };
+
+    let level = ((player.compleation / config.compleation_per_level).floor().max(0.) as u32).min(config.max_level);
+    if director.level != level {
+        director.level = level;
+    }
+}
+
+fn trigger_spawners(
+    mut spawners: Query<(&Spawner, &SpawnerArea, &mut SpawnerState, &GlobalTransform)>,
+    player_q: Query<&GlobalTransform, With<PlayerMover>>,
+    mut director: ResMut<WaveDirector>,
+    config: Res<WaveConfig>,
+) {
+    let Ok(player_t) = player_q.get_single() else {
+        return;
+    };
+    let player_position = player_t.translation().truncate();
+
+    for (spawner, area, mut state, transform) in spawners.iter_mut() {
+        if !matches!(*state, SpawnerState::Waiting)
+            || !area.contains(transform.translation().truncate(), player_position)
+        {
+            continue;
+        }
+
+        *state = SpawnerState::Running(director.wave(spawner, &config));
+        director.waves_started += 1;
+    }
+}
+
+fn run_spawners(
+    mut commands: Commands,
+    mut spawners: Query<(Entity, &Spawner, &SpawnerArea, &mut SpawnerState, &GlobalTransform)>,
+    spawned: Query<&SpawnedBy, (With<Enemy>, Without<Dead>)>,
+    templates: Templates,
+    mut cleared: EventWriter<WaveCleared>,
+    mut rng: ResMut<GameRng>,
+    time: Res<Time>,
+) {
+    if !templates.is_loaded() {
+        return;
+    }
+
+    for (entity, spawner, area, mut state, transform) in spawners.iter_mut() {
+        let SpawnerState::Running(wave) = state.as_mut() else {
+            continue;
+        };
+        let alive = spawned.iter().filter(|spawned_by| spawned_by.0 == entity).count() as i32;
+
+        if wave.remaining <= 0 {
+            if alive == 0 {
+                *state = SpawnerState::Cleared;
+                cleared.send(WaveCleared { spawner: entity });
+            }
+            continue;
+        }
+
+        let crowded = spawner.max_alive > 0 && alive >= spawner.max_alive;
+        if !wave.cooldown.tick(time.delta()).finished() || crowded {
+            continue;
+        }
+        let Some(template) = templates.get(&spawner.template) else {
+            warn!("No enemy template named '{}'", spawner.template);
+            *state = SpawnerState::Cleared;
+            continue;
+        };
+
+        let centre = transform.translation();
+        let offset = Vec2::new(
+            rng.range_f32(-area.half_size.x, area.half_size.x),
+            rng.range_f32(-area.half_size.y, area.half_size.y),
+        );
+        let name = match template.names.len() {
+            0 => String::new(),
+            len => template.names[rng.range_i32(0, len as i32 - 1) as usize].clone(),
+        };
+
+        commands.spawn((
+            Enemy {
+                inventory: ItemTypeVec(template.inventory.clone()),
+                hp: template.hp * wave.hp_factor,
+                attack: template.attack,
+                fear: template.fear,
+                name,
+                sprite: template.sprite.clone(),
+                loot: template.loot.clone(),
+                ..Default::default()
+            },
+            SpatialBundle::from_transform(Transform::from_translation(centre + offset.extend(0.))),
+            enemy_bundle(),
+            SpawnedBy(entity),
+        ));
+
+        wave.remaining -= 1;
+        wave.cooldown = Timer::from_seconds(wave.interval, TimerMode::Once);
+    }
+}
+
+// Enemies don't outlive the spawner they came from, so reloading a level
+// doesn't leave its waves behind.
+fn despawn_orphans(
+    mut commands: Commands,
+    spawned: Query<(Entity, &SpawnedBy)>,
+    spawners: Query<(), With<Spawner>>,
+) {
+    for (entity, spawned_by) in spawned.iter() {
+        if !spawners.contains(spawned_by.0) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use bevy::prelude::*;
+use crate::actor::{Player, PLAYER_RADIUS};
+use crate::aim::AimDirection;
+use crate::inventory::Inventory;
+use crate::projectile::{projectile_bundle, Projectile, ProjectileSet, ProjectileTarget};
+use crate::shop::shop_closed;
+use crate::util::*;
+
+pub struct SpellsPlugin;
+
+impl Plugin for SpellsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpellConfig>()
+            .register_type::<SpellConfig>()
+            .register_type::<Spellbook>()
+            .add_systems(Update, (
+                init_spellbook,
+                read_scrolls,
+                regenerate_mp,
+                cast_spells.run_if(shop_closed),
+            ).chain().before(ProjectileSet));
+    }
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct SpellConfig {
+    pub projectile_speed: f32,
+    /// Seconds before a projectile that hit nothing fizzles out.
+    pub projectile_lifetime: f32,
+    /// MP regained per second, up to the MP the player started with.
+    pub mp_regen: f32,
+}
+
+impl Default for SpellConfig {
+    fn default() -> Self {
+        Self {
+            projectile_speed: 220.,
+            projectile_lifetime: 1.,
+            mp_regen: 2.,
+        }
+    }
+}
+
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Spell {
+    /// A single fast bolt.
+    Bolt,
+    /// Three weaker bolts in a fan.
+    Scatter,
+}
+
+impl Spell {
+    /// The order scrolls teach spells in.
+    pub const ALL: [Spell; 2] = [Spell::Bolt, Spell::Scatter];
+
+    pub fn mp_cost(&self) -> f32 {
+        match self {
+            Spell::Bolt => 5.,
+            Spell::Scatter => 12.,
+        }
+    }
+
+    pub fn cooldown(&self) -> f32 {
+        match self {
+            Spell::Bolt => 0.4,
+            Spell::Scatter => 1.,
+        }
+    }
+
+    pub fn damage(&self) -> f32 {
+        match self {
+            Spell::Bolt => 2.,
+            Spell::Scatter => 1.,
+        }
+    }
+
+    pub fn knockback(&self) -> f32 {
+        match self {
+            Spell::Bolt => 60.,
+            Spell::Scatter => 40.,
+        }
+    }
+
+    /// Angles, relative to the aim, of the projectiles the spell fires.
+    fn spread(&self) -> &'static [f32] {
+        match self {
+            Spell::Bolt => &[0.],
+            Spell::Scatter => &[-0.25, 0., 0.25],
+        }
+    }
+}
+
+/// Spells the player knows. Lives next to `Player`, which holds the MP.
+#[derive(Component, Reflect)]
+pub struct Spellbook {
+    pub known: Vec<Spell>,
+    pub selected: usize,
+    pub max_mp: f32,
+    cooldown: Timer,
+}
+
+impl Spellbook {
+    pub fn new(max_mp: f32) -> Self {
+        Self {
+            known: Vec::new(),
+            selected: 0,
+            max_mp,
+            cooldown: Timer::from_seconds(0., TimerMode::Once),
+        }
+    }
+
+    pub fn selected_spell(&self) -> Option<Spell> {
+        self.known.get(self.selected).copied()
+    }
+
+    pub fn ready(&self) -> bool {
+        self.cooldown.finished()
+    }
+
+    /// Learns the first spell not known yet, returns `None` if there is
+    /// nothing left to learn.
+    pub fn learn_next(&mut self) -> Option<Spell> {
+        let spell = Spell::ALL.into_iter().find(|spell| !self.known.contains(spell))?;
+        self.known.push(spell);
+        self.selected = self.known.len() - 1;
+        Some(spell)
+    }
+}
+
+fn init_spellbook(
+    mut commands: Commands,
+    players: Query<(Entity, &Player), (Added<Player>, Without<Spellbook>)>,
+) {
+    for (entity, player) in players.iter() {
+        commands.entity(entity).insert(Spellbook::new(player.mp));
+    }
+}
+
+// Q reads a scroll from the inventory and learns a new spell from it.
+fn read_scrolls(
+    input: Res<ButtonInput<KeyCode>>,
+    mut player_q: Query<(&mut Inventory, &mut Spellbook), With<Player>>,
+) {
+    if !input.just_pressed(KeyCode::KeyQ) {
+        return;
+    }
+    let Ok((mut inventory, mut spellbook)) = player_q.get_single_mut() else {
+        return;
+    };
+
+    if inventory.count(ItemType::Scroll) == 0 {
+        return;
+    }
+    if let Some(spell) = spellbook.learn_next() {
+        inventory.remove(ItemType::Scroll, 1);
+        info!("Learned {:?}", spell);
+    }
+}
+
+fn regenerate_mp(
+    mut player_q: Query<(&mut Player, &mut Spellbook)>,
+    config: Res<SpellConfig>,
+    time: Res<Time>,
+) {
+    for (mut player, mut spellbook) in player_q.iter_mut() {
+        spellbook.cooldown.tick(time.delta());
+
+        if player.mp < spellbook.max_mp {
+            player.mp = (player.mp + config.mp_regen * time.delta_seconds()).min(spellbook.max_mp);
+        }
+    }
+}
+
+fn cast_spells(
+    mut commands: Commands,
+    buttons: Res<ButtonInput<MouseButton>>,
+    mut player_q: Query<(&mut Player, &mut Spellbook)>,
+    mover_q: Query<(Entity, &GlobalTransform, &AimDirection), With<PlayerMover>>,
+    config: Res<SpellConfig>,
+) {
+    if !buttons.just_pressed(MouseButton::Right) {
+        return;
+    }
+    let Ok((mut player, mut spellbook)) = player_q.get_single_mut() else {
+        return;
+    };
+    let Ok((caster, transform, aim)) = mover_q.get_single() else {
+        return;
+    };
+    let Some(spell) = spellbook.selected_spell() else {
+        return;
+    };
+    if !spellbook.ready() || player.mp < spell.mp_cost() {
+        return;
+    }
+
+    player.mp -= spell.mp_cost();
+    spellbook.cooldown = Timer::from_seconds(spell.cooldown(), TimerMode::Once);
+
+    let position = transform.translation().truncate();
+
+    for angle in spell.spread() {
+        let direction = Vec2::from_angle(*angle).rotate(aim.0);
+        let projectile = Projectile::new(
+            caster,
+            ProjectileTarget::Enemies,
+            spell.damage(),
+            spell.knockback(),
+            config.projectile_lifetime,
+        );
+
+        commands.spawn(projectile_bundle(projectile, position, PLAYER_RADIUS, direction * config.projectile_speed));
+    }
+}
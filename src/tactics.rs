@@ -0,0 +1,240 @@
+use std::f32::consts::TAU;
+
+use bevy::{prelude::*, utils::HashMap};
+use crate::actor::{Cower, Dead, Enemy, Follow, Idle, Patrol, ReturnToPost, Surrender, Wander};
+use crate::animation::{AnimState, AnimationController};
+use crate::morale::Morale;
+use crate::movement::{MovementController, MovementSet};
+use crate::perception::Perception;
+use crate::targeting::{Target, Targets};
+use crate::util::*;
+
+pub struct TacticsPlugin;
+
+impl Plugin for TacticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TacticsConfig>()
+            .register_type::<TacticsConfig>()
+            .add_systems(Update, (
+                surround,
+                squad_cohesion,
+                coordinated_retreat,
+                separation,
+            ).chain().in_set(MovementSet::Adjust));
+    }
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct TacticsConfig {
+    /// Enemies closer than this push away from each other.
+    pub separation_radius: f32,
+    pub separation_weight: f32,
+    /// Squad members further than this from their leader catch up.
+    pub cohesion_radius: f32,
+    pub cohesion_weight: f32,
+    /// How far from the target followers spread out around it.
+    pub surround_radius: f32,
+    /// Average squad fear at which the whole squad falls back, and below
+    /// which it stops again.
+    pub retreat_above: f32,
+    pub regroup_below: f32,
+    pub retreat_speed: f32,
+}
+
+impl Default for TacticsConfig {
+    fn default() -> Self {
+        Self {
+            separation_radius: 14.,
+            separation_weight: 1.5,
+            cohesion_radius: 60.,
+            cohesion_weight: 0.5,
+            surround_radius: 20.,
+            retreat_above: 40.,
+            regroup_below: 20.,
+            retreat_speed: 25.,
+        }
+    }
+}
+
+/// The enemy's squad is falling back from the player together.
+#[derive(Component)]
+pub struct Retreating;
+
+// Followers of the same target each take their own angle around it instead
+// of all walking to its centre. Slots keep the order the followers already
+// stand in, so nobody has to cross the circle.
+fn surround(
+    mut followers: Query<(Entity, &Follow, Option<&Perception>, &GlobalTransform, &mut MovementController), Without<Dead>>,
+    targets: Targets,
+    config: Res<TacticsConfig>,
+) {
+    let mut by_target: HashMap<Target, Vec<(Entity, f32)>> = HashMap::new();
+
+    for (entity, follow, _, transform, _) in followers.iter() {
+        let Some(target) = targets.position(follow.target) else {
+            continue;
+        };
+        let offset = transform.translation().truncate() - target;
+        by_target
+            .entry(follow.target)
+            .or_default()
+            .push((entity, offset.y.atan2(offset.x)));
+    }
+
+    for (target, mut group) in by_target {
+        if group.len() < 2 {
+            continue;
+        }
+        let Some(target) = targets.position(target) else {
+            continue;
+        };
+
+        group.sort_by(|(a_entity, a), (b_entity, b)| a.total_cmp(b).then(a_entity.cmp(b_entity)));
+        let start = group[0].1;
+        let step = TAU / group.len() as f32;
+
+        for (slot, (entity, _)) in group.iter().enumerate() {
+            let Ok((_, _, perception, transform, mut controller)) = followers.get_mut(*entity) else {
+                continue;
+            };
+            // Each follower circles where it thinks the target is.
+            let centre = perception.map_or(target, |perception| perception.chase_point(target));
+            let goal = centre + Vec2::from_angle(start + step * slot as f32) * config.surround_radius;
+            controller.input = (goal - transform.translation().truncate()).normalize_or_zero();
+        }
+    }
+}
+
+fn squads<'a>(enemies: impl Iterator<Item = (Entity, &'a Enemy)>) -> HashMap<&'a str, Vec<Entity>> {
+    let mut squads: HashMap<&str, Vec<Entity>> = HashMap::new();
+
+    for (entity, enemy) in enemies {
+        if !enemy.squad.is_empty() {
+            squads.entry(enemy.squad.as_str()).or_default().push(entity);
+        }
+    }
+
+    squads
+}
+
+// Squad members that stray too far from their leader, or from the middle of
+// the squad once the leader is gone, walk back to it. A member that is
+// minding its own business joins a leader that went after the player.
+// Members that are following already close in on the same target, so they
+// are left to `surround`.
+fn squad_cohesion(
+    enemies: Query<(Entity, &Enemy, &GlobalTransform, Has<Follow>), (Without<Dead>, Without<Cower>, Without<Surrender>)>,
+    mut controllers: Query<(&mut MovementController, &Children)>,
+    mut anims: Query<&mut AnimationController>,
+    calm: Query<(), Or<(With<Idle>, With<Patrol>, With<Wander>, With<ReturnToPost>)>>,
+    config: Res<TacticsConfig>,
+) {
+    for (_, members) in squads(enemies.iter().map(|(entity, enemy, ..)| (entity, enemy))) {
+        let positions: Vec<_> = members
+            .iter()
+            .filter_map(|member| enemies.get(*member).ok())
+            .collect();
+
+        let leader = positions.iter().find(|(_, enemy, ..)| enemy.squad_leader);
+        let centre = leader.map_or_else(
+            || positions.iter().map(|(_, _, transform, _)| transform.translation().truncate()).sum::<Vec2>() / positions.len() as f32,
+            |(_, _, transform, _)| transform.translation().truncate(),
+        );
+        let leader_following = leader.is_some_and(|(.., following)| *following);
+
+        for (entity, enemy, transform, following) in positions.iter() {
+            if enemy.squad_leader || *following {
+                continue;
+            }
+            let Ok((mut controller, children)) = controllers.get_mut(*entity) else {
+                continue;
+            };
+
+            let to_centre = centre - transform.translation().truncate();
+            let joining = leader_following && calm.contains(*entity);
+            if to_centre.length() < config.cohesion_radius && !joining {
+                continue;
+            }
+
+            controller.input = (controller.input + to_centre.normalize_or_zero() * config.cohesion_weight)
+                .clamp_length_max(1.);
+
+            for child in children {
+                if let Ok(mut anim) = anims.get_mut(*child) {
+                    anim.set_base(AnimState::Walk);
+                }
+            }
+        }
+    }
+}
+
+// A squad that is scared enough on average backs off from the player as a
+// group, even the members that would still fight on their own.
+fn coordinated_retreat(
+    mut commands: Commands,
+    enemies: Query<(Entity, &Enemy, &Morale, Has<Retreating>), (Without<Dead>, Without<Cower>, Without<Surrender>)>,
+    mut movers: Query<(&GlobalTransform, &mut MovementController)>,
+    player_q: Query<Entity, With<PlayerMover>>,
+    config: Res<TacticsConfig>,
+) {
+    let Ok(player) = player_q.get_single() else {
+        return;
+    };
+    let Ok(player_position) = movers.get(player).map(|(transform, _)| transform.translation().truncate()) else {
+        return;
+    };
+
+    for (_, members) in squads(enemies.iter().map(|(entity, enemy, ..)| (entity, enemy))) {
+        let fear = members
+            .iter()
+            .filter_map(|member| enemies.get(*member).ok())
+            .map(|(_, _, morale, _)| morale.fear)
+            .sum::<f32>() / members.len() as f32;
+
+        for member in members {
+            let Ok((.., retreating)) = enemies.get(member) else {
+                continue;
+            };
+
+            if fear >= config.retreat_above && !retreating {
+                commands.entity(member).insert(Retreating);
+            } else if fear < config.regroup_below && retreating {
+                commands.entity(member).remove::<Retreating>();
+                continue;
+            } else if !retreating {
+                continue;
+            }
+
+            let Ok((transform, mut controller)) = movers.get_mut(member) else {
+                continue;
+            };
+            controller.input = (transform.translation().truncate() - player_position).normalize_or_zero();
+            controller.max_speed = Some(config.retreat_speed);
+        }
+    }
+}
+
+// Moving enemies keep a little room between each other.
+fn separation(
+    mut movers: Query<(Entity, &GlobalTransform, &mut MovementController), (With<Enemy>, Without<Dead>)>,
+    others: Query<(Entity, &GlobalTransform), (With<Enemy>, Without<Dead>)>,
+    config: Res<TacticsConfig>,
+) {
+    for (entity, transform, mut controller) in movers.iter_mut() {
+        if controller.input == Vec2::ZERO {
+            continue;
+        }
+        let position = transform.translation().truncate();
+
+        let push = others
+            .iter()
+            .filter(|(other, _)| *other != entity)
+            .map(|(_, other_t)| position - other_t.translation().truncate())
+            .filter(|away| away.length() < config.separation_radius)
+            .map(|away| away.normalize_or_zero() * (1. - away.length() / config.separation_radius))
+            .sum::<Vec2>();
+
+        controller.input = (controller.input + push * config.separation_weight).clamp_length_max(1.);
+    }
+}
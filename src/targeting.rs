@@ -0,0 +1,99 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use seldom_state::prelude::*;
+use crate::actor::{Cower, Dead, Enemy, Flee, Follow, Idle, Patrol, ReturnToPost, Surrender, Wander};
+use crate::level::LevelLoaded;
+use crate::util::*;
+
+pub struct TargetingPlugin;
+
+impl Plugin for TargetingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ResetAi>()
+            .add_systems(Update, (
+                player_replaced,
+                level_loaded,
+                reset_ai,
+            ).chain());
+    }
+}
+
+/// Who an AI state is after. States hold this instead of an `Entity`, and
+/// it is looked up again every frame through `Targets`, so a despawned or
+/// replaced player never leaves a state pointing at nothing.
+#[derive(Clone, Copy, Reflect, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Target {
+    #[default]
+    Player,
+}
+
+/// AI states that are after a `Target`.
+pub trait Targeting {
+    fn target(&self) -> Target;
+}
+
+/// Resolves a `Target` to whatever currently fills that role.
+#[derive(SystemParam)]
+pub struct Targets<'w, 's> {
+    player: Query<'w, 's, (Entity, &'static GlobalTransform), With<PlayerMover>>,
+}
+
+impl<'w, 's> Targets<'w, 's> {
+    pub fn entity(&self, target: Target) -> Option<Entity> {
+        match target {
+            Target::Player => self.player.get_single().ok().map(|(entity, _)| entity),
+        }
+    }
+
+    pub fn position(&self, target: Target) -> Option<Vec2> {
+        match target {
+            Target::Player => self
+                .player
+                .get_single()
+                .ok()
+                .map(|(_, transform)| transform.translation().truncate()),
+        }
+    }
+}
+
+/// Trigger for when the target of state `S` can't be found, so the state
+/// machine can fall back to `Idle` instead of chasing nothing.
+pub fn target_lost<S: Component + Targeting>(In(entity): In<Entity>, states: Query<&S>, targets: Targets) -> bool {
+    states
+        .get(entity)
+        .map_or(true, |state| targets.entity(state.target()).is_none())
+}
+
+/// Throws away every enemy state machine so `enemy_ai` builds fresh ones.
+/// Sent after level reloads.
+#[derive(Event)]
+pub struct ResetAi;
+
+// A new player body means every plan made about the old one is stale.
+fn player_replaced(players: Query<(), Added<PlayerMover>>, enemies: Query<(), With<StateMachine>>, mut resets: EventWriter<ResetAi>) {
+    if !players.is_empty() && !enemies.is_empty() {
+        resets.send(ResetAi);
+    }
+}
+
+// Enemies that made it through a reload can't trust what they knew.
+fn level_loaded(mut loaded: EventReader<LevelLoaded>, mut resets: EventWriter<ResetAi>) {
+    if loaded.read().count() > 0 {
+        resets.send(ResetAi);
+    }
+}
+
+fn reset_ai(
+    mut commands: Commands,
+    mut resets: EventReader<ResetAi>,
+    enemies: Query<Entity, (With<Enemy>, With<StateMachine>, Without<Dead>)>,
+) {
+    if resets.read().count() == 0 {
+        return;
+    }
+
+    for entity in enemies.iter() {
+        commands
+            .entity(entity)
+            .remove::<(StateMachine, Idle, Follow, Flee, Patrol, Wander, ReturnToPost, Cower, Surrender)>();
+    }
+}
@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 use bevy_entitiles_derive::{LdtkEntity, LdtkEntityTag, LdtkEnum};
+use serde::Deserialize;
 
 
 #[derive(Component)]
@@ -11,7 +12,16 @@ pub struct PlayerSprite;
 #[derive(Component)]
 pub struct EnemySprite;
 
-#[derive(LdtkEnum, Reflect, Clone, Copy, Debug)]
+#[derive(Component)]
+pub struct AttackSprite;
+
+#[derive(Component)]
+pub struct AttackPivot;
+
+#[derive(Component)]
+pub struct AddSprite;
+
+#[derive(LdtkEnum, Reflect, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
 #[wrapper_derive(Reflect, Default)]
 pub enum ItemType {
     Key,
@@ -22,14 +32,25 @@ pub enum ItemType {
     Soul,
 }
 
-#[derive(LdtkEnum, Reflect, Clone, Copy, Debug)]
+impl ItemType {
+    pub const ALL: [ItemType; 6] = [
+        ItemType::Key,
+        ItemType::Coins,
+        ItemType::Scroll,
+        ItemType::Bandage,
+        ItemType::Sword,
+        ItemType::Soul,
+    ];
+}
+
+#[derive(LdtkEnum, Reflect, Clone, Copy, PartialEq, Eq, Debug)]
 #[wrapper_derive(Reflect, Default)]
 pub enum ChestState {
     Open,
     Closed,
 }
 
-#[derive(LdtkEnum, Reflect, Clone, Copy, Debug)]
+#[derive(LdtkEnum, Reflect, Clone, Copy, PartialEq, Eq, Debug)]
 #[wrapper_derive(Reflect, Default)]
 pub enum DoorState {
     Open,
@@ -49,6 +70,9 @@ pub struct Item {
 pub struct Chest {
     #[ldtk_name = "state"]
     pub state: ChestState,
+    /// Name of the loot table rolled when the chest is opened.
+    #[ldtk_default]
+    pub loot: String,
 }
 
 #[derive(Component, LdtkEntity, Reflect)]
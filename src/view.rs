@@ -0,0 +1,316 @@
+use bevy::prelude::*;
+use bevy_asepritesheet::prelude::*;
+use bevy_yarnspinner::prelude::*;
+use bevy_yarnspinner_example_dialogue_view::prelude::*;
+use crate::actor::Enemy;
+use crate::animation::{AnimState, AnimationController, ClipEnd};
+use crate::boss::Boss;
+use crate::combat::SWING_DURATION;
+use crate::corpse::DroppedItem;
+use crate::interact::NearestInteractable;
+use crate::shop::Merchant;
+use crate::projectile::{Projectile, ProjectileTarget};
+use crate::sheets::SpritesheetRegistry;
+use crate::util::*;
+
+const DEFAULT_ENEMY_SPRITE: &str = "enemy.json";
+// Bosses reuse enemy spritesheets, drawn bigger.
+const BOSS_SCALE: f32 = 2.;
+
+/// Sprites and dialogue UI. Everything here needs a renderer, so it is kept
+/// out of the gameplay plugins that run headless in tests.
+pub struct ViewPlugin;
+
+impl Plugin for ViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+                AsepritesheetPlugin::new(&["sprite.json"]).in_schedule(Update),
+                YarnSpinnerPlugin::new(),
+                ExampleYarnSpinnerDialogueViewPlugin::new(),
+            ))
+            .init_resource::<ItemIcons>()
+            .add_systems(Startup, spawn_interact_prompt)
+            .add_systems(
+                Update,
+                (
+                    player_add_sprites,
+                    enemy_add_sprites,
+                    merchant_add_sprites,
+                    boss_add_sprites,
+                    item_add_sprites,
+                    projectile_add_sprites,
+                    chest_sprites,
+                    door_sprites,
+                    update_interact_prompt,
+                ),
+            );
+    }
+}
+
+fn attack_animations() -> AnimationController {
+    AnimationController::default()
+        .with_clip(AnimState::Attack, "attack", ClipEnd::Stop)
+        .with_duration(AnimState::Attack, SWING_DURATION)
+}
+
+fn player_animations() -> AnimationController {
+    AnimationController::default()
+        .with_clip(AnimState::Idle, "idle", ClipEnd::Hold)
+        .with_clip(AnimState::Walk, "walk", ClipEnd::Loop)
+        .with_clip(AnimState::Hit, "hit", ClipEnd::Hold)
+}
+
+fn enemy_animations() -> AnimationController {
+    AnimationController::default()
+        .with_clip(AnimState::Idle, "idle", ClipEnd::Hold)
+        .with_clip(AnimState::Walk, "walk", ClipEnd::Loop)
+        .with_clip(AnimState::Hit, "hit", ClipEnd::Hold)
+        .with_clip(AnimState::Dead, "dead", ClipEnd::Hold)
+}
+
+fn player_add_sprites(
+    mut commands: Commands,
+    player_q: Query<Entity, Added<PlayerSprite>>,
+    attack_q: Query<Entity, Added<AttackSprite>>,
+    assets: Res<AssetServer>,
+    mut registry: ResMut<SpritesheetRegistry>,
+) {
+    for entity in attack_q.iter() {
+        let sheet_handle = registry.get_or_load(&mut commands, &assets, "sprite.json");
+
+        commands.entity(entity).insert((
+            AnimatedSpriteBundle {
+                spritesheet: sheet_handle,
+                ..Default::default()
+            },
+            attack_animations(),
+        ));
+    }
+
+    for entity in player_q.iter() {
+        let sheet_handle = registry.get_or_load(&mut commands, &assets, "character.json");
+
+        commands.entity(entity).insert((
+            AnimatedSpriteBundle {
+                spritesheet: sheet_handle,
+                ..Default::default()
+            },
+            player_animations(),
+        ));
+    }
+}
+
+fn enemy_add_sprites(
+    mut commands: Commands,
+    enemy_q: Query<(Entity, &Enemy), With<AddSprite>>,
+    assets: Res<AssetServer>,
+    mut registry: ResMut<SpritesheetRegistry>,
+) {
+    for (enemy, data) in enemy_q.iter() {
+        let path = match data.sprite.as_str() {
+            "" => DEFAULT_ENEMY_SPRITE,
+            path => path,
+        };
+        let sheet_handle = registry.get_or_load(&mut commands, &assets, path);
+
+        let anim = commands
+            .spawn((
+                AnimatedSpriteBundle {
+                    spritesheet: sheet_handle,
+                    ..Default::default()
+                },
+                enemy_animations(),
+                EnemySprite,
+            ))
+            .id();
+
+        commands.entity(enemy).add_child(anim);
+        commands.entity(enemy).remove::<AddSprite>();
+    }
+}
+
+fn merchant_add_sprites(
+    mut commands: Commands,
+    merchant_q: Query<Entity, Added<Merchant>>,
+    assets: Res<AssetServer>,
+    mut registry: ResMut<SpritesheetRegistry>,
+) {
+    for merchant in merchant_q.iter() {
+        let sheet_handle = registry.get_or_load(&mut commands, &assets, "character.json");
+
+        let anim = commands
+            .spawn((
+                AnimatedSpriteBundle {
+                    spritesheet: sheet_handle,
+                    ..Default::default()
+                },
+                AnimationController::default().with_clip(AnimState::Idle, "idle", ClipEnd::Hold),
+            ))
+            .id();
+
+        commands.entity(merchant).add_child(anim);
+    }
+}
+
+fn boss_add_sprites(
+    mut commands: Commands,
+    boss_q: Query<(Entity, &Boss), Added<Boss>>,
+    assets: Res<AssetServer>,
+    mut registry: ResMut<SpritesheetRegistry>,
+) {
+    for (boss, data) in boss_q.iter() {
+        let path = match data.sprite.as_str() {
+            "" => DEFAULT_ENEMY_SPRITE,
+            path => path,
+        };
+        let sheet_handle = registry.get_or_load(&mut commands, &assets, path);
+
+        let anim = commands
+            .spawn((
+                AnimatedSpriteBundle {
+                    spritesheet: sheet_handle,
+                    ..Default::default()
+                },
+                enemy_animations(),
+            ))
+            .insert(Transform::from_scale(Vec3::splat(BOSS_SCALE)))
+            .id();
+
+        commands.entity(boss).add_child(anim);
+    }
+}
+
+/// Item icons from the 16x16 grid of `ldtk/tileset.png`, the same ones the
+/// `ItemType` enum uses in LDtk.
+#[derive(Resource)]
+pub struct ItemIcons {
+    pub texture: Handle<Image>,
+    pub layout: Handle<TextureAtlasLayout>,
+}
+
+impl FromWorld for ItemIcons {
+    fn from_world(world: &mut World) -> Self {
+        let texture = world.resource::<AssetServer>().load("ldtk/tileset.png");
+        let layout = world
+            .resource_mut::<Assets<TextureAtlasLayout>>()
+            .add(TextureAtlasLayout::from_grid(Vec2::splat(16.), 16, 16, None, None));
+
+        Self { texture, layout }
+    }
+}
+
+impl ItemIcons {
+    pub fn atlas(&self, itype: ItemType) -> TextureAtlas {
+        let index = match itype {
+            ItemType::Key => 7,
+            ItemType::Sword => 8,
+            ItemType::Soul => 9,
+            ItemType::Coins => 23,
+            ItemType::Scroll => 24,
+            ItemType::Bandage => 25,
+        };
+
+        TextureAtlas {
+            layout: self.layout.clone(),
+            index,
+        }
+    }
+}
+
+fn item_add_sprites(
+    mut commands: Commands,
+    items: Query<(Entity, &Item), Added<DroppedItem>>,
+    icons: Res<ItemIcons>,
+) {
+    for (entity, item) in items.iter() {
+        commands.entity(entity).insert((
+            Sprite::default(),
+            icons.texture.clone(),
+            icons.atlas(item.itype),
+        ));
+    }
+}
+
+// The player's spells are small and blue, boss projectiles bigger and red.
+fn projectile_add_sprites(
+    mut commands: Commands,
+    projectiles: Query<(Entity, &Projectile), Added<Projectile>>,
+) {
+    for (entity, projectile) in projectiles.iter() {
+        let (color, size) = match projectile.target {
+            ProjectileTarget::Enemies => (Color::rgb(0.5, 0.8, 1.), 4.),
+            ProjectileTarget::Player => (Color::rgb(1., 0.35, 0.3), 5.),
+        };
+
+        commands.entity(entity).insert((
+            Sprite {
+                color,
+                custom_size: Some(Vec2::splat(size)),
+                ..Default::default()
+            },
+            Handle::<Image>::default(),
+        ));
+    }
+}
+
+#[derive(Component)]
+struct InteractPrompt;
+
+fn spawn_interact_prompt(mut commands: Commands) {
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section("", TextStyle {
+                font_size: 8.,
+                ..Default::default()
+            }),
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+        InteractPrompt,
+    ));
+}
+
+// Show what E does, e.g. "E: pick up <item>", above whatever it would act on
+// right now.
+fn update_interact_prompt(
+    nearest: Res<NearestInteractable>,
+    targets: Query<(&GlobalTransform, Option<&Item>, Option<&Merchant>, Option<&Enemy>)>,
+    mut prompt_q: Query<(&mut Text, &mut Transform, &mut Visibility), With<InteractPrompt>>,
+) {
+    let Ok((mut text, mut transform, mut visibility)) = prompt_q.get_single_mut() else {
+        return;
+    };
+    let Some((target_t, item, merchant, enemy)) = nearest.0.and_then(|entity| targets.get(entity).ok()) else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+
+    text.sections[0].value = match (item, merchant, enemy) {
+        (Some(item), ..) => format!("E: pick up {:?} x{}", item.itype, item.count),
+        (_, Some(merchant), _) => format!("E: talk to {}", merchant.name),
+        (.., Some(enemy)) => format!("E: spare {}", enemy.name),
+        _ => "E: open".to_string(),
+    };
+    transform.translation = target_t.translation() + Vec3::new(0., 14., 10.);
+    *visibility = Visibility::Visible;
+}
+
+// Swap to the open chest tile once a chest has been opened.
+fn chest_sprites(mut chests: Query<(&Chest, &mut TextureAtlas), Changed<Chest>>) {
+    for (chest, mut atlas) in chests.iter_mut() {
+        atlas.index = match chest.state {
+            ChestState::Closed => 6,
+            ChestState::Open => 22,
+        };
+    }
+}
+
+// Doors open and shut during boss fights.
+fn door_sprites(mut doors: Query<(&Door, &mut TextureAtlas), Changed<Door>>) {
+    for (door, mut atlas) in doors.iter_mut() {
+        atlas.index = match door.state {
+            DoorState::Closed => 18,
+            DoorState::Open => 34,
+        };
+    }
+}
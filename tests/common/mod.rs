@@ -0,0 +1,396 @@
+use std::time::Duration;
+
+use bevy::{
+    ecs::system::{CommandQueue, EntityCommands},
+    prelude::*,
+    time::TimeUpdateStrategy,
+    utils::HashMap,
+};
+use bevy_entitiles::ldtk::{
+    json::{field::FieldInstance, level::EntityInstance},
+    resources::LdtkAssets,
+    traits::LdtkEntity,
+};
+use bevy_xpbd_2d::prelude::*;
+use serde_json::Value;
+use acerola_jam_0_returner::{
+    actor::{enemy_bundle, Enemy, Player},
+    boss::{boss_bundle, Boss, BossDefs, BossSet},
+    corpse::DroppedItem,
+    inventory::Inventory,
+    level::{LevelLoaded, LoadLevel},
+    loot::{LootTableSet, LootTables},
+    replay::{InputRecorder, InputReplay, Recording},
+    rng::GameRng,
+    shop::Merchant,
+    spawner::{EnemyTemplateSet, EnemyTemplates, Spawner},
+    util::{Chest, ChestState, Door, DoorState, Item, ItemType, PlayerMover},
+    GameplayPlugins,
+};
+
+const LDTK_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/ldtk/test.ldtk");
+pub const FRAME: f32 = 1. / 60.;
+pub const SEED: u64 = 0x5EED;
+
+/// A headless game: gameplay and physics plugins on top of `MinimalPlugins`,
+/// stepped one fixed frame at a time with simulated input.
+pub struct TestGame {
+    pub app: App,
+}
+
+impl TestGame {
+    pub fn new() -> Self {
+        let mut app = App::new();
+
+        app.add_plugins((
+                MinimalPlugins,
+                AssetPlugin::default(),
+                TransformPlugin,
+                HierarchyPlugin,
+                PhysicsPlugins::default(),
+                GameplayPlugins,
+            ))
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<ButtonInput<MouseButton>>()
+            .init_resource::<TestLevel>()
+            .add_systems(PostUpdate, load_requested_levels)
+            .insert_resource(GameRng::new(SEED))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(FRAME)));
+
+        // Run the startup schedule.
+        app.update();
+
+        let mut game = Self { app };
+        game.wait_for_data();
+        game
+    }
+
+    // Loot tables, bosses and enemy templates are real assets, give the
+    // asset server a moment to load them before anything can die, be opened,
+    // be fought or be spawned.
+    fn wait_for_data(&mut self) {
+        for _ in 0..500 {
+            let loot = self.app.world.resource::<LootTables>().0.clone();
+            let bosses = self.app.world.resource::<BossDefs>().0.clone();
+            let templates = self.app.world.resource::<EnemyTemplates>().0.clone();
+            if self.app.world.resource::<Assets<LootTableSet>>().contains(&loot)
+                && self.app.world.resource::<Assets<BossSet>>().contains(&bosses)
+                && self.app.world.resource::<Assets<EnemyTemplateSet>>().contains(&templates)
+            {
+                return;
+            }
+
+            std::thread::sleep(Duration::from_millis(2));
+            self.app.update();
+        }
+
+        panic!("loot tables, bosses or enemy templates didn't load");
+    }
+
+    /// Loads an LDtk level the way `LoadLevel` does in the tests, see
+    /// `spawn_level`.
+    pub fn load_level(&mut self, identifier: &str) -> &mut Self {
+        spawn_level(&mut self.app.world, identifier);
+        self.step();
+        self
+    }
+
+    /// Starts recording input from the next frame on.
+    pub fn record(&mut self) -> &mut Self {
+        let seed = self.app.world.resource::<GameRng>().seed();
+        self.app.world.insert_resource(InputRecorder::new(seed, FRAME));
+        self
+    }
+
+    pub fn recording(&self) -> Recording {
+        self.app.world.resource::<InputRecorder>().recording.clone()
+    }
+
+    /// Feeds a recording back in, one frame per `step`.
+    pub fn replay(&mut self, recording: Recording) -> &mut Self {
+        self.app.world.insert_resource(GameRng::new(recording.seed));
+        self.app.world.insert_resource(InputReplay::new(recording));
+        self
+    }
+
+    pub fn spawn_item(&mut self, itype: ItemType, count: i32, position: Vec2) -> Entity {
+        self.app
+            .world
+            .spawn((
+                Item { itype, count },
+                Transform::from_translation(position.extend(0.)),
+                GlobalTransform::default(),
+            ))
+            .id()
+    }
+
+    pub fn spawn_enemy(&mut self, position: Vec2) -> Entity {
+        self.app
+            .world
+            .spawn((
+                Enemy {
+                    hp: 10.,
+                    name: "Test".to_string(),
+                    ..Default::default()
+                },
+                Transform::from_translation(position.extend(0.)),
+                GlobalTransform::default(),
+                enemy_bundle(),
+            ))
+            .id()
+    }
+
+    pub fn spawn_boss(&mut self, def: &str, position: Vec2) -> Entity {
+        self.app
+            .world
+            .spawn((
+                Boss {
+                    def: def.to_string(),
+                    ..Default::default()
+                },
+                Transform::from_translation(position.extend(0.)),
+                GlobalTransform::default(),
+                boss_bundle(),
+            ))
+            .id()
+    }
+
+    pub fn spawn_chest(&mut self, loot: &str, position: Vec2) -> Entity {
+        self.app
+            .world
+            .spawn((
+                Chest {
+                    state: ChestState::Closed,
+                    loot: loot.to_string(),
+                },
+                Transform::from_translation(position.extend(0.)),
+                GlobalTransform::default(),
+            ))
+            .id()
+    }
+
+    /// Items dropped at runtime, by corpses or chests.
+    pub fn dropped_items(&mut self) -> Vec<(ItemType, i32)> {
+        self.app
+            .world
+            .query_filtered::<&Item, With<DroppedItem>>()
+            .iter(&self.app.world)
+            .map(|item| (item.itype, item.count))
+            .collect()
+    }
+
+    /// Advances one fixed frame, then clears `just_pressed`/`just_released`
+    /// like the input plugin would at the start of the next frame.
+    pub fn step(&mut self) -> &mut Self {
+        self.app.update();
+        self.app.world.resource_mut::<ButtonInput<KeyCode>>().clear();
+        self.app.world.resource_mut::<ButtonInput<MouseButton>>().clear();
+        self
+    }
+
+    pub fn steps(&mut self, frames: usize) -> &mut Self {
+        for _ in 0..frames {
+            self.step();
+        }
+        self
+    }
+
+    pub fn press(&mut self, key: KeyCode) -> &mut Self {
+        self.app.world.resource_mut::<ButtonInput<KeyCode>>().press(key);
+        self
+    }
+
+    pub fn release(&mut self, key: KeyCode) -> &mut Self {
+        self.app.world.resource_mut::<ButtonInput<KeyCode>>().release(key);
+        self
+    }
+
+    /// Presses and releases a key over two frames.
+    pub fn tap(&mut self, key: KeyCode) -> &mut Self {
+        self.press(key).step().release(key).step()
+    }
+
+    pub fn click(&mut self, button: MouseButton) -> &mut Self {
+        self.app.world.resource_mut::<ButtonInput<MouseButton>>().press(button);
+        self.step();
+        self.app.world.resource_mut::<ButtonInput<MouseButton>>().release(button);
+        self.step()
+    }
+
+    pub fn enemies(&mut self) -> Vec<Entity> {
+        self.app
+            .world
+            .query_filtered::<Entity, With<Enemy>>()
+            .iter(&self.app.world)
+            .collect()
+    }
+
+    pub fn player(&mut self) -> &Player {
+        self.app.world.query::<&Player>().single(&self.app.world)
+    }
+
+    pub fn player_mut(&mut self) -> Mut<Player> {
+        self.app.world.query::<&mut Player>().single_mut(&mut self.app.world)
+    }
+
+    pub fn inventory(&mut self) -> &Inventory {
+        self.app
+            .world
+            .query_filtered::<&Inventory, With<Player>>()
+            .single(&self.app.world)
+    }
+
+    pub fn inventory_mut(&mut self) -> Mut<Inventory> {
+        self.app
+            .world
+            .query_filtered::<&mut Inventory, With<Player>>()
+            .single_mut(&mut self.app.world)
+    }
+
+    /// Where every door is, its state and whether it blocks the way.
+    pub fn doors(&mut self) -> Vec<(Vec2, DoorState, bool)> {
+        self.app
+            .world
+            .query::<(&Door, &Transform, Has<Collider>)>()
+            .iter(&self.app.world)
+            .map(|(door, transform, collider)| (transform.translation.truncate(), door.state, collider))
+            .collect()
+    }
+
+    pub fn boss(&mut self) -> Entity {
+        self.app
+            .world
+            .query_filtered::<Entity, With<Boss>>()
+            .single(&self.app.world)
+    }
+
+    pub fn spawner(&mut self) -> Entity {
+        self.app
+            .world
+            .query_filtered::<Entity, With<Spawner>>()
+            .single(&self.app.world)
+    }
+
+    pub fn merchant(&mut self) -> Entity {
+        self.app
+            .world
+            .query_filtered::<Entity, With<Merchant>>()
+            .single(&self.app.world)
+    }
+
+    pub fn enemy_named(&mut self, name: &str) -> Entity {
+        self.app
+            .world
+            .query::<(Entity, &Enemy)>()
+            .iter(&self.app.world)
+            .find(|(_, enemy)| enemy.name == name)
+            .map(|(entity, _)| entity)
+            .unwrap_or_else(|| panic!("no enemy named {}", name))
+    }
+
+    /// Moves the player body, keeping physics in sync.
+    pub fn teleport_player(&mut self, position: Vec2) -> &mut Self {
+        let mut mover = self
+            .app
+            .world
+            .query_filtered::<(&mut Transform, &mut Position, &mut LinearVelocity), With<PlayerMover>>();
+        let (mut transform, mut physics_position, mut velocity) = mover.single_mut(&mut self.app.world);
+
+        transform.translation = position.extend(transform.translation.z);
+        physics_position.0 = position;
+        velocity.0 = Vec2::ZERO;
+        self
+    }
+
+    pub fn enemy(&self, entity: Entity) -> &Enemy {
+        self.app.world.get::<Enemy>(entity).unwrap()
+    }
+
+    pub fn enemy_mut(&mut self, entity: Entity) -> Mut<Enemy> {
+        self.app.world.get_mut::<Enemy>(entity).unwrap()
+    }
+}
+
+/// Entities of the loaded level, despawned when the next one loads. The
+/// global player isn't one of them, like in LDtk.
+#[derive(Resource, Default)]
+struct TestLevel(Vec<Entity>);
+
+// Stands in for `MapPlugin`, which needs a renderer.
+fn load_requested_levels(world: &mut World) {
+    let requests: Vec<_> = world.resource_mut::<Events<LoadLevel>>().drain().collect();
+    if let Some(request) = requests.last() {
+        spawn_level(world, &request.identifier);
+    }
+}
+
+/// Replaces the loaded level with the entities of an LDtk level, spawned
+/// through the same `LdtkEntity` impls and callbacks the game registers.
+/// The tilemap and sprites are left out, they need a renderer.
+fn spawn_level(world: &mut World, identifier: &str) {
+    for entity in std::mem::take(&mut world.resource_mut::<TestLevel>().0) {
+        if let Some(entity) = world.get_entity_mut(entity) {
+            entity.despawn_recursive();
+        }
+    }
+
+    let json: Value = serde_json::from_str(&std::fs::read_to_string(LDTK_PATH).unwrap()).unwrap();
+    let level = json["levels"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|level| level["identifier"] == identifier)
+        .unwrap_or_else(|| panic!("no level named {}", identifier));
+    let has_player = world.query_filtered::<(), With<Player>>().iter(world).count() > 0;
+
+    let mut entities = Vec::new();
+    for layer in level["layerInstances"].as_array().unwrap() {
+        for instance in layer["entityInstances"].as_array().unwrap() {
+            let mut instance = instance.clone();
+            instance["__tile"] = Value::Null;
+            let instance: EntityInstance = serde_json::from_value(instance).unwrap();
+
+            if instance.identifier == "Player" && has_player {
+                continue;
+            }
+            if let Some(entity) = spawn_instance(world, &instance) {
+                if instance.identifier != "Player" {
+                    entities.push(entity);
+                }
+            }
+        }
+    }
+
+    world.resource_mut::<TestLevel>().0 = entities;
+    world.send_event(LevelLoaded { identifier: identifier.to_string() });
+}
+
+fn spawn_instance(world: &mut World, instance: &EntityInstance) -> Option<Entity> {
+    let initialize: fn(&mut EntityCommands, &EntityInstance, &HashMap<String, FieldInstance>, &AssetServer, &LdtkAssets) =
+        match instance.identifier.as_str() {
+            "Player" => Player::initialize,
+            "Enemy" => Enemy::initialize,
+            "Merchant" => Merchant::initialize,
+            "Door" => Door::initialize,
+            "Boss" => Boss::initialize,
+            "Spawner" => Spawner::initialize,
+            "Item" => Item::initialize,
+            _ => return None,
+        };
+    let fields = instance
+        .field_instances
+        .iter()
+        .map(|field| (field.identifier.clone(), field.clone()))
+        .collect::<HashMap<_, _>>();
+    let position = Vec3::new(instance.local_pos[0] as f32, -instance.local_pos[1] as f32, 0.);
+    let asset_server = world.resource::<AssetServer>().clone();
+
+    let mut queue = CommandQueue::default();
+    let mut commands = Commands::new(&mut queue, world);
+    let mut entity_commands = commands.spawn(SpatialBundle::from_transform(Transform::from_translation(position)));
+    let entity = entity_commands.id();
+    initialize(&mut entity_commands, instance, &fields, &asset_server, &LdtkAssets::default());
+    queue.apply(world);
+
+    Some(entity)
+}
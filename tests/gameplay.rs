@@ -0,0 +1,977 @@
+mod common;
+
+use bevy::{ecs::event::ManualEventReader, prelude::*};
+use bevy_xpbd_2d::prelude::*;
+use seldom_state::prelude::StateMachine;
+use acerola_jam_0_returner::{
+    actor::{Dead, EnemyHit, Flee, Follow, Idle, Patrol, ReturnToPost, Surrender, Wander},
+    boss::{BossPhaseChanged, BossState, Engaged},
+    movement::{MovementConfig, MovementController},
+    corpse::CorpseConfig,
+    inventory::Inventory,
+    loot::{LootTableSet, LootTables},
+    ending::{Ending, EndingConfig, EndingSequence, EndingStats},
+    morale::{Morale, MoraleConfig},
+    patrol::Post,
+    perception::{Perception, PerceptionConfig},
+    pickup::ItemPickedUp,
+    profile::Profile,
+    rng::GameRng,
+    run::{Run, RunConfig, RunModifier, RunPhase},
+    shop::{Shop, ShopAction},
+    spawner::{SpawnedBy, Spawner, SpawnerState, WaveConfig, WaveDirector},
+    spells::{Spell, Spellbook},
+    tactics::Retreating,
+    replay::Recording,
+    util::{Chest, ChestState, DoorState, Item, ItemType, PlayerMover},
+};
+use common::{TestGame, FRAME};
+
+#[test]
+fn enemy_takes_damage_when_hit() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    let enemy = game.enemies()[0];
+    let hp = game.enemy(enemy).hp;
+    let fear = game.enemy(enemy).fear;
+
+    game.app.world.send_event(EnemyHit {
+        enemy,
+        attacker: Entity::PLACEHOLDER,
+        damage: 2.,
+        direction: Vec2::X,
+        knockback: 0.,
+    });
+    game.step();
+
+    assert_eq!(game.enemy(enemy).hp, hp - 2.);
+    assert!(game.enemy(enemy).fear > fear);
+}
+
+#[test]
+fn picked_up_item_ends_up_in_inventory() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    let position = game
+        .app
+        .world
+        .query_filtered::<&Transform, With<PlayerMover>>()
+        .single(&game.app.world)
+        .translation
+        .truncate();
+    game.spawn_item(ItemType::Sword, 1, position);
+
+    game.tap(KeyCode::KeyE);
+
+    assert_eq!(game.inventory().count(ItemType::Sword), 1);
+}
+
+#[test]
+fn full_compleation_triggers_end() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    game.player_mut().compleation = 96.;
+    let enemy = game.enemies()[0];
+    game.enemy_mut(enemy).hp = 0.;
+    game.steps(5);
+
+    assert!(game.player().compleation >= 100.);
+    assert_eq!(game.app.world.resource::<EndingSequence>().ending, Ending::Swift);
+    assert_eq!(game.app.world.resource::<Profile>().endings["Swift"], 1);
+}
+
+#[test]
+fn replay_reproduces_recorded_run() {
+    let player_position = |game: &mut TestGame| {
+        game.app
+            .world
+            .query_filtered::<&Transform, With<PlayerMover>>()
+            .single(&game.app.world)
+            .translation
+    };
+
+    let mut recorded = TestGame::new();
+    recorded.load_level("Start").steps(2).record();
+    recorded.press(KeyCode::KeyD).press(KeyCode::KeyW).steps(20);
+    recorded.release(KeyCode::KeyW).steps(10);
+    recorded.release(KeyCode::KeyD).steps(10);
+
+    let recording = recorded.recording();
+    let frames = recording.frames.len();
+    assert_eq!(Recording::parse(&recording.to_text()).unwrap(), recording);
+
+    let mut replayed = TestGame::new();
+    replayed.load_level("Start").steps(2).replay(recording);
+    replayed.steps(frames);
+
+    assert_eq!(player_position(&mut recorded), player_position(&mut replayed));
+}
+
+#[test]
+fn replay_makes_the_same_purchases() {
+    let at_merchant = |game: &mut TestGame| {
+        game.load_level("Start").steps(2);
+        let merchant = game.merchant();
+        let position = game.app.world.get::<Transform>(merchant).unwrap().translation.truncate();
+        game.teleport_player(position - Vec2::new(12., 0.)).step();
+        *game.inventory_mut() = Inventory::from_items(&[ItemType::Coins; 7]);
+    };
+
+    let mut recorded = TestGame::new();
+    at_merchant(&mut recorded);
+    recorded.record().tap(KeyCode::KeyE);
+    // What clicking the first offer in the shop UI sends.
+    recorded.app.world.send_event(ShopAction::Buy(0));
+    recorded.steps(5);
+    assert_eq!(recorded.inventory().count(ItemType::Bandage), 1);
+
+    let recording = recorded.recording();
+    let frames = recording.frames.len();
+    assert_eq!(Recording::parse(&recording.to_text()).unwrap(), recording);
+
+    let mut replayed = TestGame::new();
+    at_merchant(&mut replayed);
+    replayed.replay(recording).steps(frames);
+
+    assert_eq!(replayed.inventory().count(ItemType::Bandage), 1);
+    assert_eq!(replayed.inventory().count(ItemType::Coins), recorded.inventory().count(ItemType::Coins));
+}
+
+#[test]
+fn swing_hits_each_enemy_once() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    let enemy = game.enemy_named("Bob");
+    let hp = game.enemy(enemy).hp;
+    let sword_skill = game.player().sword_skill;
+
+    let enemy_position = game.app.world.get::<Transform>(enemy).unwrap().translation;
+    game.teleport_player(enemy_position.truncate() - Vec2::new(10., 0.));
+    game.steps(2);
+
+    game.click(MouseButton::Left).steps(40);
+
+    assert_eq!(game.enemy(enemy).hp, hp - sword_skill);
+}
+
+// How far Bob moves along x in 10 frames after a hit with `knockback`.
+fn knocked_back(knockback: f32) -> f32 {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    let enemy = game.enemy_named("Bob");
+    let start = game.app.world.get::<GlobalTransform>(enemy).unwrap().translation();
+
+    game.app.world.send_event(EnemyHit {
+        enemy,
+        attacker: Entity::PLACEHOLDER,
+        damage: 1.,
+        direction: Vec2::X,
+        knockback,
+    });
+    game.steps(10);
+
+    game.app.world.get::<GlobalTransform>(enemy).unwrap().translation().x - start.x
+}
+
+#[test]
+fn hit_knocks_enemy_back() {
+    // Bob walks on his own, so compare with the same hit without knockback.
+    let moved = knocked_back(100.) - knocked_back(0.);
+
+    let decay = 1. - MovementConfig::default().knockback_decay * FRAME;
+    let expected: f32 = (0..10).map(|frame| 100. * decay.powi(frame) * FRAME).sum();
+    assert!((moved - expected).abs() < 1.5, "moved {} instead of {}", moved, expected);
+}
+
+#[test]
+fn corpse_stops_colliding_and_despawns() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+    game.app.world.resource_mut::<CorpseConfig>().despawn_after = Some(0.5);
+
+    let enemy = game.enemy_named("Bob");
+    game.enemy_mut(enemy).hp = 0.;
+    game.steps(3);
+
+    assert!(game.app.world.get::<Collider>(enemy).is_none());
+
+    game.steps(60);
+    assert!(game.app.world.get_entity(enemy).is_none());
+}
+
+#[test]
+fn loot_rolls_are_reproducible() {
+    let game = TestGame::new();
+    let handle = &game.app.world.resource::<LootTables>().0;
+    let tables = game.app.world.resource::<Assets<LootTableSet>>().get(handle).unwrap();
+
+    let first = tables.roll("chest", &mut GameRng::new(7));
+    let second = tables.roll("chest", &mut GameRng::new(7));
+
+    assert_eq!(first, second);
+    assert!(first.iter().any(|(itype, count)| *itype == ItemType::Coins && *count >= 3));
+    assert!(tables.roll("missing", &mut GameRng::new(7)).is_empty());
+}
+
+#[test]
+fn opening_chest_drops_its_loot() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    let position = game
+        .app
+        .world
+        .query_filtered::<&Transform, With<PlayerMover>>()
+        .single(&game.app.world)
+        .translation
+        .truncate();
+    let chest = game.spawn_chest("chest", position + Vec2::new(8., 0.));
+
+    game.tap(KeyCode::KeyE);
+
+    assert_eq!(game.app.world.get::<Chest>(chest).unwrap().state, ChestState::Open);
+    assert!(game.dropped_items().iter().any(|(itype, _)| *itype == ItemType::Coins));
+}
+
+#[test]
+fn e_picks_up_only_the_nearest_item() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    let position = game
+        .app
+        .world
+        .query_filtered::<&Transform, With<PlayerMover>>()
+        .single(&game.app.world)
+        .translation
+        .truncate();
+    let keys = game.inventory().count(ItemType::Key);
+    game.spawn_item(ItemType::Sword, 1, position + Vec2::new(2., 0.));
+    let key = game.spawn_item(ItemType::Key, 1, position + Vec2::new(10., 0.));
+
+    game.tap(KeyCode::KeyE);
+
+    assert_eq!(game.inventory().count(ItemType::Sword), 1);
+    assert_eq!(game.inventory().count(ItemType::Key), keys);
+    assert!(game.app.world.get_entity(key).is_some());
+}
+
+#[test]
+fn e_only_acts_on_the_nearest_target() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    let position = game
+        .app
+        .world
+        .query_filtered::<&Transform, With<PlayerMover>>()
+        .single(&game.app.world)
+        .translation
+        .truncate();
+    let chest = game.spawn_chest("chest", position + Vec2::new(12., 0.));
+    game.spawn_item(ItemType::Sword, 1, position + Vec2::new(2., 0.));
+    game.step();
+
+    game.tap(KeyCode::KeyE);
+    assert_eq!(game.inventory().count(ItemType::Sword), 1);
+    assert_eq!(game.app.world.get::<Chest>(chest).unwrap().state, ChestState::Closed);
+
+    // With the item gone the chest is next.
+    game.tap(KeyCode::KeyE);
+    assert_eq!(game.app.world.get::<Chest>(chest).unwrap().state, ChestState::Open);
+}
+
+#[test]
+fn coins_are_pulled_in_and_collected() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    let position = game
+        .app
+        .world
+        .query_filtered::<&Transform, With<PlayerMover>>()
+        .single(&game.app.world)
+        .translation
+        .truncate();
+    let coins = game.inventory().count(ItemType::Coins);
+    game.spawn_item(ItemType::Coins, 3, position + Vec2::new(40., 0.));
+
+    let mut reader = ManualEventReader::<ItemPickedUp>::default();
+    let mut picked_up = Vec::new();
+    for _ in 0..30 {
+        game.step();
+        let events = game.app.world.resource::<Events<ItemPickedUp>>();
+        picked_up.extend(reader.read(events).map(|event| (event.itype, event.count)));
+    }
+
+    assert_eq!(game.inventory().count(ItemType::Coins), coins + 3);
+    assert_eq!(picked_up, vec![(ItemType::Coins, 3)]);
+}
+
+#[test]
+fn inventory_stacks_items_and_equips_the_sword() {
+    let mut inventory = Inventory::from_items(&[ItemType::Sword, ItemType::Coins, ItemType::Coins, ItemType::Sword]);
+
+    assert_eq!(inventory.equipped, Some(ItemType::Sword));
+    assert_eq!(inventory.count(ItemType::Sword), 2);
+    assert_eq!(inventory.count(ItemType::Coins), 2);
+    assert_eq!(inventory.stacks().len(), 2);
+
+    // Bandages stack up to 10, so 25 of them take three slots.
+    assert_eq!(inventory.add(ItemType::Bandage, 25), 0);
+    assert_eq!(inventory.stacks().len(), 5);
+
+    inventory.capacity = 5;
+    assert_eq!(inventory.add(ItemType::Bandage, 10), 5);
+    assert_eq!(inventory.count(ItemType::Bandage), 30);
+
+    assert!(inventory.remove(ItemType::Bandage, 12));
+    assert!(!inventory.remove(ItemType::Bandage, 100));
+    assert_eq!(inventory.count(ItemType::Bandage), 18);
+
+    let mut items = inventory.to_items();
+    items.sort_by_key(|item| *item as u8);
+    let mut round_trip = Inventory::from_items(&items).to_items();
+    round_trip.sort_by_key(|item| *item as u8);
+    assert_eq!(items, round_trip);
+}
+
+#[test]
+fn full_inventory_leaves_the_rest_on_the_ground() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    let position = game
+        .app
+        .world
+        .query_filtered::<&Transform, With<PlayerMover>>()
+        .single(&game.app.world)
+        .translation
+        .truncate();
+    let mut inventory = game.inventory_mut();
+    *inventory = Inventory {
+        capacity: 1,
+        ..Default::default()
+    };
+    let item = game.spawn_item(ItemType::Bandage, 25, position);
+
+    game.tap(KeyCode::KeyE);
+
+    assert_eq!(game.inventory().count(ItemType::Bandage), 10);
+    assert_eq!(game.app.world.get::<Item>(item).unwrap().count, 15);
+}
+
+#[test]
+fn buying_and_selling_at_a_merchant() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    let merchant = game.merchant();
+    let position = game.app.world.get::<Transform>(merchant).unwrap().translation.truncate();
+    game.teleport_player(position - Vec2::new(12., 0.)).step();
+    *game.inventory_mut() = Inventory::from_items(&[ItemType::Coins; 7]);
+
+    game.tap(KeyCode::KeyE);
+    assert_eq!(game.app.world.resource::<Shop>().open, Some(merchant));
+
+    // Greta sells bandages for 5 and buys them back for half.
+    game.app.world.send_event(ShopAction::Buy(0));
+    game.step();
+    assert_eq!(game.inventory().count(ItemType::Bandage), 1);
+    assert_eq!(game.inventory().count(ItemType::Coins), 2);
+
+    game.app.world.send_event(ShopAction::Buy(0));
+    game.step();
+    assert_eq!(game.inventory().count(ItemType::Bandage), 1);
+
+    game.app.world.send_event(ShopAction::Sell(ItemType::Bandage));
+    game.step();
+    assert_eq!(game.inventory().count(ItemType::Bandage), 0);
+    assert_eq!(game.inventory().count(ItemType::Coins), 4);
+
+    game.app.world.send_event(ShopAction::Close);
+    game.step();
+    assert_eq!(game.app.world.resource::<Shop>().open, None);
+}
+
+#[test]
+fn scroll_teaches_a_spell_that_hits_enemies() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    let enemy = game.enemy_named("Bob");
+    let hp = game.enemy(enemy).hp;
+    let fear = game.enemy(enemy).fear;
+    let mp = game.player().mp;
+
+    let enemy_position = game.app.world.get::<Transform>(enemy).unwrap().translation;
+    game.teleport_player(enemy_position.truncate() - Vec2::new(40., 0.));
+    game.inventory_mut().add(ItemType::Scroll, 1);
+
+    // Nothing to cast before reading the scroll.
+    game.click(MouseButton::Right).steps(20);
+    assert_eq!(game.enemy(enemy).hp, hp);
+
+    game.tap(KeyCode::KeyQ);
+    assert_eq!(game.inventory().count(ItemType::Scroll), 0);
+    let mut spellbook = game.app.world.query::<&Spellbook>();
+    assert_eq!(spellbook.single(&game.app.world).known, vec![Spell::Bolt]);
+
+    game.click(MouseButton::Right).steps(20);
+
+    assert_eq!(game.enemy(enemy).hp, hp - Spell::Bolt.damage());
+    assert!(game.enemy(enemy).fear > fear);
+    assert!(game.player().mp < mp);
+}
+
+#[test]
+fn fear_decays_and_spreads_to_allies() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    let bob = game.enemy_named("Bob");
+    let rick = game.enemy_named("Rick");
+
+    game.enemy_mut(rick).fear = 20.;
+    game.steps(60);
+    let decayed = game.enemy(rick).fear;
+    assert!(decayed < 20. && decayed > 0.);
+
+    // Allies nearby make Rick braver than his raw fear.
+    let morale = game.app.world.get::<Morale>(rick).unwrap().fear;
+    assert!(morale < decayed);
+
+    game.enemy_mut(bob).hp = 0.;
+    game.steps(3);
+    assert!(game.enemy(rick).fear > decayed);
+}
+
+#[test]
+fn terrified_enemy_surrenders() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    let enemy = game.enemy_named("Rick");
+    let position = game.app.world.get::<Transform>(enemy).unwrap().translation.truncate();
+    game.teleport_player(position - Vec2::new(20., 0.));
+    game.enemy_mut(enemy).fear = 500.;
+
+    game.steps(10);
+
+    assert!(game.app.world.get::<Surrender>(enemy).is_some());
+}
+
+#[test]
+fn followers_spread_out_around_the_player() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    let bob = game.enemy_named("Bob");
+    let rick = game.enemy_named("Rick");
+    game.enemy_mut(bob).fear = 0.;
+    game.enemy_mut(rick).fear = 0.;
+    game.teleport_player(Vec2::new(330., -216.));
+    // Swinging makes sure both of them notice, whichever way they face.
+    game.click(MouseButton::Left);
+
+    game.steps(120);
+
+    let player = game
+        .app
+        .world
+        .query_filtered::<&Transform, With<PlayerMover>>()
+        .single(&game.app.world)
+        .translation
+        .truncate();
+    let offset = |game: &TestGame, enemy| game.app.world.get::<Transform>(enemy).unwrap().translation.truncate() - player;
+    let angle = offset(&game, bob).angle_between(offset(&game, rick)).abs();
+
+    assert!(angle > std::f32::consts::FRAC_PI_2, "followers bunched up at {} radians", angle);
+}
+
+#[test]
+fn scared_squad_retreats_together() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    let bob = game.enemy_named("Bob");
+    let rick = game.enemy_named("Rick");
+    let start = game.app.world.get::<Transform>(rick).unwrap().translation.truncate();
+    game.teleport_player(start - Vec2::new(100., 0.));
+
+    // Only Bob is scared, but it is enough for the squad on average.
+    game.enemy_mut(bob).fear = 200.;
+    game.enemy_mut(rick).fear = 0.;
+    game.steps(60);
+
+    assert!(game.app.world.get::<Retreating>(rick).is_some());
+    assert!(game.app.world.get::<Transform>(rick).unwrap().translation.x > start.x);
+}
+
+#[test]
+fn enemies_patrol_their_route_or_wander() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    let philip = game.enemy_named("Philip");
+    let bob = game.enemy_named("Bob");
+    let waypoint = game.app.world.get::<Post>(philip).unwrap().anchor();
+    let distance = |game: &TestGame| {
+        game.app.world.get::<Transform>(philip).unwrap().translation.truncate().distance(waypoint)
+    };
+    let start = distance(&game);
+
+    game.steps(120);
+
+    assert!(game.app.world.get::<Patrol>(philip).is_some());
+    assert!(distance(&game) < start);
+    assert!(game.app.world.get::<Wander>(bob).is_some());
+}
+
+#[test]
+fn enemy_returns_to_post_after_losing_the_player() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    let rick = game.enemy_named("Rick");
+    let home = game.app.world.get::<Post>(rick).unwrap().home;
+    game.enemy_mut(rick).fear = 0.;
+
+    game.teleport_player(home - Vec2::new(40., 0.)).click(MouseButton::Left).steps(30);
+    assert!(game.app.world.get::<Follow>(rick).is_some());
+
+    // Rick checks where the player was last, then gives up.
+    let memory = game.app.world.resource::<PerceptionConfig>().memory;
+    game.teleport_player(home + Vec2::new(300., 0.)).steps((memory / FRAME) as usize + 20);
+    assert!(game.app.world.get::<ReturnToPost>(rick).is_some());
+
+    game.steps(240);
+    assert!(game.app.world.get::<ReturnToPost>(rick).is_none());
+    assert!(game.app.world.get::<Follow>(rick).is_none());
+}
+
+#[test]
+fn walls_block_sight_but_not_noise() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+    let player = Vec2::new(240., -300.);
+    game.teleport_player(player);
+
+    game.app.world.spawn((
+        RigidBody::Static,
+        Collider::rectangle(4., 12.),
+        TransformBundle::from_transform(Transform::from_translation((player - Vec2::new(10., 0.)).extend(0.))),
+    ));
+    let hidden = game.spawn_enemy(player - Vec2::new(20., 0.));
+    let visible = game.spawn_enemy(player + Vec2::new(0., 20.));
+    game.steps(2);
+
+    assert!(!game.app.world.get::<Perception>(hidden).unwrap().sees_player);
+    assert!(game.app.world.get::<Perception>(visible).unwrap().sees_player);
+
+    game.click(MouseButton::Left);
+
+    let perception = game.app.world.get::<Perception>(hidden).unwrap();
+    assert!(!perception.sees_player);
+    assert!(perception.aware());
+}
+
+#[test]
+fn scared_chaser_flees_instead_of_going_home() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    let rick = game.enemy_named("Rick");
+    let home = game.app.world.get::<Post>(rick).unwrap().home;
+    game.enemy_mut(rick).fear = 0.;
+    game.teleport_player(home - Vec2::new(40., 0.)).click(MouseButton::Left).steps(10);
+    assert!(game.app.world.get::<Follow>(rick).is_some());
+
+    // Above `flee_above` even with a nearby ally's courage, below `cower_above`.
+    game.enemy_mut(rick).fear = 75.;
+    game.steps(3);
+
+    assert!(game.app.world.get::<Flee>(rick).is_some());
+    assert!(game.app.world.get::<ReturnToPost>(rick).is_none());
+}
+
+#[test]
+fn enemies_cope_with_the_player_being_replaced() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    let rick = game.enemy_named("Rick");
+    let home = game.app.world.get::<Post>(rick).unwrap().home;
+    game.enemy_mut(rick).fear = 0.;
+    game.teleport_player(home - Vec2::new(40., 0.)).click(MouseButton::Left).steps(10);
+    assert!(game.app.world.get::<Follow>(rick).is_some());
+
+    let mover = game
+        .app
+        .world
+        .query_filtered::<Entity, With<PlayerMover>>()
+        .single(&game.app.world);
+    game.app.world.entity_mut(mover).despawn_recursive();
+    game.step();
+    assert!(game.app.world.get::<Follow>(rick).is_none());
+    assert!(game.app.world.get::<Idle>(rick).is_some());
+    game.steps(4);
+
+    // A new body right next to Rick gets noticed and chased like the old one.
+    game.app.world.spawn((
+        PlayerMover,
+        RigidBody::Dynamic,
+        Collider::circle(5.),
+        MovementController::default(),
+        TransformBundle::from_transform(Transform::from_translation((home - Vec2::new(15., 0.)).extend(0.))),
+    ));
+    game.steps(10);
+    assert!(game.app.world.get::<Follow>(rick).is_some());
+}
+
+#[test]
+fn sparing_a_surrendered_enemy_counts_as_mercy() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    let enemy = game.enemy_named("Rick");
+    let position = game.app.world.get::<Transform>(enemy).unwrap().translation.truncate();
+    game.teleport_player(position - Vec2::new(12., 0.));
+    game.enemy_mut(enemy).fear = 500.;
+    game.steps(10);
+    let compleation = game.player().compleation;
+
+    game.tap(KeyCode::KeyE);
+
+    assert!(game.app.world.get_entity(enemy).is_none());
+    assert_eq!(game.player().spared, 1);
+    assert_eq!(game.player().killed, 0);
+    assert_eq!(game.player().compleation, compleation);
+}
+
+#[test]
+fn sparing_everyone_ends_the_game_peacefully() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    for enemy in game.enemies() {
+        game.enemy_mut(enemy).hp = 1.;
+        let position = game.app.world.get::<Transform>(enemy).unwrap().translation.truncate();
+        game.teleport_player(position).step();
+        game.tap(KeyCode::KeyE);
+    }
+
+    assert!(game.enemies().is_empty());
+    assert_eq!(game.player().killed, 0);
+    assert_eq!(game.app.world.resource::<EndingSequence>().ending, Ending::Pacifist);
+    assert_eq!(game.app.world.resource::<Profile>().endings["Pacifist"], 1);
+}
+
+#[test]
+fn endings_follow_the_players_choices() {
+    let config = EndingConfig::default();
+    let stats = EndingStats { kills: 25, spares: 0, items: 10, time: 600. };
+
+    assert_eq!(Ending::choose(&EndingStats { kills: 0, spares: 3, ..stats }, &config), Ending::Pacifist);
+    assert_eq!(Ending::choose(&EndingStats { spares: 1, ..stats }, &config), Ending::Merciful);
+    assert_eq!(Ending::choose(&EndingStats { time: 100., ..stats }, &config), Ending::Swift);
+    assert_eq!(Ending::choose(&EndingStats { items: 80, ..stats }, &config), Ending::Hoarder);
+    assert_eq!(Ending::choose(&stats, &config), Ending::Returner);
+}
+
+#[test]
+fn profile_survives_a_round_trip() {
+    let mut profile = Profile::default();
+    profile.record_ending("Swift", 120.);
+    profile.record_ending("Pacifist", 300.);
+    profile.record_ending("Swift", 90.5);
+
+    let parsed = Profile::parse(&profile.to_text()).unwrap();
+
+    assert_eq!(parsed, profile);
+    assert_eq!(parsed.endings["Swift"], 2);
+    assert_eq!(parsed.best_time, Some(90.5));
+}
+
+// The entrance door authored in the warden's arena in the start level.
+const ARENA_DOOR: Vec2 = Vec2::new(936., -232.);
+
+#[test]
+fn walking_into_a_boss_arena_locks_it() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    let boss = game.boss();
+    let position = game.app.world.get::<Transform>(boss).unwrap().translation.truncate();
+    let radius = game.app.world.get::<BossState>(boss).unwrap().def.arena_radius;
+    let before = game.doors();
+    let (_, state, _) = before.iter().find(|(door, ..)| *door == ARENA_DOOR).unwrap();
+    assert_eq!(*state, DoorState::Open);
+
+    game.teleport_player(position - Vec2::new(radius - 10., 0.)).steps(2);
+
+    // Only the open door in the arena is locked, the closed doors behind the
+    // warden stay plain closed doors.
+    assert!(game.app.world.get::<Engaged>(boss).is_some());
+    for (door, state, collider) in game.doors() {
+        let (_, state_before, _) = before.iter().find(|(other, ..)| *other == door).unwrap();
+        if door == ARENA_DOOR {
+            assert_eq!(state, DoorState::Closed);
+            assert!(collider);
+        } else {
+            assert_eq!(state, *state_before);
+            assert!(!collider);
+        }
+    }
+
+    // Getting too close to the boss hurts.
+    let hp = game.player().hp;
+    game.teleport_player(position + Vec2::new(12., 0.)).steps(5);
+    assert!(game.player().hp < hp);
+}
+
+#[test]
+fn boss_phases_follow_its_hp() {
+    let mut game = TestGame::new();
+    let boss = game.spawn_boss("warden", Vec2::ZERO);
+    game.step();
+
+    let max_hp = game.app.world.get::<BossState>(boss).unwrap().hp;
+    let mut reader = ManualEventReader::<BossPhaseChanged>::default();
+    let mut changes = Vec::new();
+    let mut phases = Vec::new();
+
+    // Down to 75%, 57.5% and 27.5% of the warden's HP.
+    for damage in [0.25, 0.175, 0.3] {
+        game.app.world.send_event(EnemyHit {
+            enemy: boss,
+            attacker: Entity::PLACEHOLDER,
+            damage: damage * max_hp,
+            direction: Vec2::X,
+            knockback: 0.,
+        });
+        game.step();
+
+        let events = game.app.world.resource::<Events<BossPhaseChanged>>();
+        changes.extend(reader.read(events).map(|event| event.phase));
+        phases.push(game.app.world.get::<BossState>(boss).unwrap().phase);
+    }
+
+    assert_eq!(phases, vec![0, 1, 2]);
+    assert_eq!(changes, vec![1, 2]);
+}
+
+#[test]
+fn beaten_boss_drops_its_reward_and_unlocks_the_arena() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    let boss = game.boss();
+    let position = game.app.world.get::<Transform>(boss).unwrap().translation.truncate();
+    let before = game.doors();
+    game.teleport_player(position - Vec2::new(30., 0.)).steps(2);
+    assert!(game.doors().contains(&(ARENA_DOOR, DoorState::Closed, true)));
+
+    let compleation = game.player().compleation;
+    game.app.world.send_event(EnemyHit {
+        enemy: boss,
+        attacker: Entity::PLACEHOLDER,
+        damage: 1000.,
+        direction: Vec2::X,
+        knockback: 0.,
+    });
+    game.steps(2);
+
+    assert!(game.app.world.get_entity(boss).is_none());
+    assert!(game.dropped_items().iter().any(|(itype, _)| *itype == ItemType::Soul));
+    assert_eq!(game.player().compleation, compleation + 20.);
+    assert_eq!(game.player().killed, 1);
+    for (door, state, collider) in game.doors() {
+        let (_, state_before, _) = before.iter().find(|(other, ..)| *other == door).unwrap();
+        assert_eq!(state, *state_before);
+        assert!(!collider);
+    }
+}
+
+// Enemies from `spawner` that are still alive.
+fn spawned_alive(game: &mut TestGame, spawner: Entity) -> Vec<Entity> {
+    game.app
+        .world
+        .query_filtered::<(Entity, &SpawnedBy), Without<Dead>>()
+        .iter(&game.app.world)
+        .filter(|(_, spawned_by)| spawned_by.0 == spawner)
+        .map(|(entity, _)| entity)
+        .collect()
+}
+
+#[test]
+fn spawner_sends_its_wave_once_the_player_walks_in() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    let spawner = game.spawner();
+    let position = game.app.world.get::<Transform>(spawner).unwrap().translation.truncate();
+    let interval = game.app.world.get::<Spawner>(spawner).unwrap().interval;
+    let wait = (interval / FRAME) as usize + 5;
+    assert!(spawned_alive(&mut game, spawner).is_empty());
+
+    game.teleport_player(position).steps(2);
+    assert_eq!(spawned_alive(&mut game, spawner).len(), 1);
+
+    // The second one comes after the interval, the third waits until one of
+    // the first two is gone.
+    game.steps(wait * 2);
+    let alive = spawned_alive(&mut game, spawner);
+    assert_eq!(alive.len(), 2);
+
+    game.enemy_mut(alive[0]).hp = 0.;
+    game.steps(wait);
+    assert_eq!(spawned_alive(&mut game, spawner).len(), 2);
+
+    for enemy in spawned_alive(&mut game, spawner) {
+        game.enemy_mut(enemy).hp = 0.;
+    }
+    game.steps(wait);
+
+    assert!(spawned_alive(&mut game, spawner).is_empty());
+    assert!(matches!(game.app.world.get::<SpawnerState>(spawner).unwrap(), SpawnerState::Cleared));
+}
+
+#[test]
+fn waves_grow_with_compleation() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+
+    let spawner = game.spawner();
+    let position = game.app.world.get::<Transform>(spawner).unwrap().translation.truncate();
+    game.player_mut().compleation = 60.;
+    game.teleport_player(position).steps(2);
+
+    assert_eq!(game.app.world.resource::<WaveDirector>().level, 2);
+    let SpawnerState::Running(wave) = game.app.world.get::<SpawnerState>(spawner).unwrap() else {
+        panic!("the spawner didn't start");
+    };
+    // Three in the LDtk wave, two more for the level, one already out.
+    assert_eq!(wave.remaining, 4);
+    assert_eq!(wave.hp_factor, 1.5);
+
+    let enemy = spawned_alive(&mut game, spawner)[0];
+    assert_eq!(game.enemy(enemy).hp, 15.);
+
+    let config = WaveConfig::default();
+    let director = WaveDirector { level: 1, ..Default::default() };
+    let spawner = Spawner { template: "grunt".to_string(), count: 2, interval: 2., max_alive: 2 };
+    assert_eq!(director.wave(&spawner, &config).remaining, 3);
+    assert!(director.wave(&spawner, &config).interval < spawner.interval);
+}
+
+fn clear_level(game: &mut TestGame) {
+    for enemy in game.enemies() {
+        game.app.world.send_event(EnemyHit {
+            enemy,
+            attacker: Entity::PLACEHOLDER,
+            damage: 1000.,
+            direction: Vec2::X,
+            knockback: 0.,
+        });
+    }
+    game.steps(2);
+}
+
+#[test]
+fn clearing_the_level_finishes_the_run() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+    let enemies = game.enemies().len() as i32;
+
+    clear_level(&mut game);
+
+    // The boss is optional.
+    let boss = game.boss();
+    assert!(game.app.world.get::<BossState>(boss).unwrap().hp > 0.);
+    let run = game.app.world.resource::<Run>();
+    assert_eq!(run.phase, RunPhase::Summary);
+    let summary = run.summary.unwrap();
+    assert_eq!(summary.run, 1);
+    assert_eq!(summary.kills, enemies);
+    assert_eq!(summary.spares, 0);
+    assert_eq!(summary.compleation, 4. * enemies as f32);
+    assert_eq!(run.next_modifiers.len(), 1);
+    assert!(game.app.world.get_resource::<EndingSequence>().is_none());
+}
+
+// Clears the Start level and starts the next run with `modifiers`.
+fn next_run(game: &mut TestGame, modifiers: Vec<RunModifier>) {
+    clear_level(game);
+    game.app.world.resource_mut::<Run>().next_modifiers = modifiers;
+    game.tap(KeyCode::KeyR);
+}
+
+#[test]
+fn next_run_keeps_only_what_carries_over() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+    let hp = game.player().hp;
+
+    clear_level(&mut game);
+    game.player_mut().hp = 10.;
+    let compleation = game.player().compleation;
+    let killed = game.player().killed;
+    game.app.world.resource_mut::<Run>().next_modifiers = Vec::new();
+    game.tap(KeyCode::KeyR);
+
+    let run = game.app.world.resource::<Run>();
+    assert_eq!(run.number, 2);
+    assert_eq!(run.phase, RunPhase::Playing);
+    assert!(run.modifiers.is_empty());
+    assert!(run.summary.is_none());
+    assert_eq!(game.player().hp, hp);
+    assert_eq!(game.player().compleation, compleation);
+    assert_eq!(game.player().killed, killed);
+
+    // Frail caps the HP the next run starts with.
+    next_run(&mut game, vec![RunModifier::Frail]);
+    assert_eq!(game.app.world.resource::<Run>().number, 3);
+    assert_eq!(game.app.world.resource::<Run>().modifiers, vec![RunModifier::Frail]);
+    assert_eq!(game.player().hp, hp * RunConfig::default().frail_hp);
+}
+
+#[test]
+fn next_run_reloads_the_level() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+    let enemies = game.enemies().len();
+    let bob = game.enemy_named("Bob");
+    let hp = game.enemy(bob).hp;
+
+    next_run(&mut game, vec![RunModifier::Hardy]);
+    game.steps(2);
+
+    assert_eq!(game.enemies().len(), enemies);
+    let bob = game.enemy_named("Bob");
+    assert_eq!(game.enemy(bob).hp, hp * RunConfig::default().hardy_hp);
+    assert!(game.app.world.get::<StateMachine>(bob).is_some());
+    assert_eq!(game.app.world.resource::<Run>().phase, RunPhase::Playing);
+}
+
+#[test]
+fn run_modifiers_toughen_new_enemies() {
+    let mut game = TestGame::new();
+    game.load_level("Start").steps(2);
+    game.app.world.resource_mut::<Run>().modifiers = vec![RunModifier::Hardy, RunModifier::Bold];
+
+    let enemy = game.spawn_enemy(Vec2::new(400., -150.));
+    game.enemy_mut(enemy).fear = 20.;
+    game.step();
+
+    // Modifiers apply first, then the frame's fear decay.
+    let config = RunConfig::default();
+    let decay = MoraleConfig::default().decay * FRAME;
+    assert_eq!(game.enemy(enemy).hp, 10. * config.hardy_hp);
+    assert!((game.enemy(enemy).fear - (20. * config.bold_fear - decay)).abs() < 1e-4);
+}